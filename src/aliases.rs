@@ -0,0 +1,81 @@
+use crate::{log, settings, types::*};
+use std::{collections::HashMap, fs, sync::OnceLock};
+
+static ALIASES: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+/// Alias keys are matched ignoring case and surrounding whitespace.
+fn normalize_alias(alias: &str) -> String {
+    alias.trim().to_lowercase()
+}
+
+fn warn(message: String) {
+    log::warning("aliases", message).send();
+}
+
+/// Parses a JSON object mapping aliases to library IDs. Entries whose value is
+/// not a `/org/project` ID are skipped.
+fn parse_aliases(source: &str, json: &str) -> HashMap<String, String> {
+    let map: HashMap<String, String> = match serde_json::from_str(json) {
+        Ok(map) => map,
+        Err(e) => {
+            warn(format!("Ignoring library aliases from {source}: {e}"));
+            return HashMap::new();
+        }
+    };
+
+    map.into_iter()
+        .filter_map(|(alias, id)| {
+            let id = id.trim();
+            if id.starts_with('/') && id.len() > 1 {
+                Some((normalize_alias(&alias), id.to_string()))
+            } else {
+                warn(format!(
+                    "Ignoring library alias '{alias}' from {source}: '{id}' is not a library ID"
+                ));
+                None
+            }
+        })
+        .collect()
+}
+
+/// Loads aliases from the file named by `LIBRARY_ALIASES_FILE`, then from the
+/// `LIBRARY_ALIASES` JSON, which wins on conflicts.
+fn aliases() -> &'static HashMap<String, String> {
+    ALIASES.get_or_init(|| {
+        let mut aliases = HashMap::new();
+        if let Some(path) = settings::get("LIBRARY_ALIASES_FILE") {
+            match fs::read_to_string(&path) {
+                Ok(json) => aliases.extend(parse_aliases(&path, &json)),
+                Err(e) => warn(format!("Failed to read library aliases from {path}: {e}")),
+            }
+        }
+        if let Some(json) = settings::get("LIBRARY_ALIASES") {
+            aliases.extend(parse_aliases("LIBRARY_ALIASES", &json));
+        }
+        aliases
+    })
+}
+
+/// Returns the library ID configured for `library_name`, if any.
+pub(crate) fn lookup(library_name: &str) -> Option<&'static str> {
+    aliases()
+        .get(&normalize_alias(library_name))
+        .map(String::as_str)
+}
+
+/// Builds a `resolve_library_id` response for an alias hit. The library is
+/// reported as a verified best match without calling the API.
+pub(crate) fn resolve(library_name: &str, library_id: &str) -> ResolveLibraryIdResponse {
+    ResolveLibraryIdResponse {
+        results: vec![Library {
+            id: library_id.to_string(),
+            title: library_name.trim().to_string(),
+            state: DocumentState::Finalized,
+            verified: Some(true),
+            ..Default::default()
+        }],
+        best_match: Some(library_id.to_string()),
+        alias: Some(library_name.trim().to_string()),
+        ..Default::default()
+    }
+}
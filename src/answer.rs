@@ -0,0 +1,122 @@
+use crate::{
+    pdk::{imports::create_message, types::*},
+    render,
+    types::*,
+};
+use std::fmt::Write;
+
+/// Most characters of documentation sent to the client's model.
+const MAX_CONTEXT_CHARS: usize = 24_000;
+const DEFAULT_MAX_TOKENS: u32 = 1024;
+
+const SYSTEM_PROMPT: &str = "You answer programming questions using only the documentation \
+excerpts provided. Do not rely on prior knowledge. Cite every statement with the bracketed ID \
+of the excerpt it comes from, e.g. [abc123]. If the excerpts do not answer the question, say so \
+briefly. Be concise: a short explanation and at most one code example.";
+
+/// A documentation excerpt offered to the model, keyed by the ID it cites.
+pub(crate) struct Excerpt {
+    pub id: String,
+    pub title: String,
+    pub body: String,
+}
+
+/// Collects the snippets of `docs` as excerpts, code first. Snippets without
+/// an ID get a positional one such as `code-2` or `info-1`.
+pub(crate) fn excerpts(docs: &QueryDocsResponse) -> Vec<Excerpt> {
+    let code = docs.code_snippets.iter().enumerate().map(|(i, s)| {
+        let mut body = String::new();
+        if !s.code_description.trim().is_empty() {
+            let _ = writeln!(body, "{}", s.code_description.trim());
+        }
+        for entry in &s.code_list {
+            render::write_code_block(&mut body, &entry.language, &entry.code);
+        }
+        Excerpt {
+            id: Some(s.code_id.trim())
+                .filter(|id| !id.is_empty())
+                .map_or_else(|| format!("code-{}", i + 1), str::to_string),
+            title: s.code_title.clone(),
+            body,
+        }
+    });
+    let info = docs.info_snippets.iter().enumerate().map(|(i, s)| Excerpt {
+        id: s
+            .page_id
+            .as_deref()
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map_or_else(|| format!("info-{}", i + 1), str::to_string),
+        title: s.breadcrumb.clone().unwrap_or_default(),
+        body: s.content.clone(),
+    });
+    code.chain(info).collect()
+}
+
+/// Lays out the excerpts for the prompt, stopping before `max_chars` is
+/// exceeded. Returns the text and the number of excerpts included.
+pub(crate) fn context(excerpts: &[Excerpt], max_chars: usize) -> (String, usize) {
+    let mut out = String::new();
+    let mut included = 0;
+    for excerpt in excerpts {
+        let heading = format!("[{}] {}", excerpt.id, excerpt.title.trim());
+        let block = format!("{}\n{}\n\n", heading.trim_end(), excerpt.body.trim_end());
+        if included > 0 && out.len() + block.len() > max_chars {
+            break;
+        }
+        out.push_str(&block);
+        included += 1;
+    }
+    (out.trim_end().to_string(), included)
+}
+
+/// Returns the excerpts cited in `answer`, in order of first citation.
+/// Citations may group several IDs, as in `[a, b]`.
+pub(crate) fn citations<'a>(answer: &str, excerpts: &'a [Excerpt]) -> Vec<&'a Excerpt> {
+    let mut cited: Vec<&Excerpt> = Vec::new();
+    let mut rest = answer;
+    while let Some(start) = rest.find('[') {
+        rest = &rest[start + 1..];
+        let Some(end) = rest.find(']') else { break };
+        for id in rest[..end].split(',').map(str::trim) {
+            if let Some(excerpt) = excerpts.iter().find(|e| e.id == id)
+                && !cited.iter().any(|c| c.id == excerpt.id)
+            {
+                cited.push(excerpt);
+            }
+        }
+        rest = &rest[end + 1..];
+    }
+    cited
+}
+
+/// Asks the client's model to answer `query` from the excerpts, returning the
+/// answer text, the model name and the number of excerpts sent.
+pub(crate) fn synthesize(
+    library_id: &str,
+    query: &str,
+    excerpts: &[Excerpt],
+    max_tokens: Option<u32>,
+) -> Result<(String, String, usize), String> {
+    let (context, included) = context(excerpts, MAX_CONTEXT_CHARS);
+    let request = CreateMessageRequestParam {
+        max_tokens: max_tokens.unwrap_or(DEFAULT_MAX_TOKENS) as i64,
+        messages: vec![SamplingMessage::Text(TextContent {
+            text: format!(
+                "Question: {query}\n\nDocumentation excerpts for {library_id}:\n\n{context}"
+            ),
+            ..Default::default()
+        })],
+        system_prompt: Some(SYSTEM_PROMPT.to_string()),
+        temperature: Some(0.2),
+        ..Default::default()
+    };
+
+    let result = create_message(request).map_err(|e| e.to_string())?;
+    match result.content {
+        SamplingMessage::Text(text) if !text.text.trim().is_empty() => {
+            Ok((text.text.trim().to_string(), result.model, included))
+        }
+        _ => Err("the model returned no text".to_string()),
+    }
+}
@@ -0,0 +1,367 @@
+use crate::{
+    log,
+    pdk::{imports::get_keyring_secret, types::*},
+    ratelimit, settings,
+};
+use chrono::Utc;
+use extism_pdk::HttpRequest;
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::Value;
+use std::{sync::Mutex, time::Duration};
+
+/// How long a pooled key is skipped after a 429 without a `Retry-After`
+/// header, or after its keyring entry could not be read.
+const DEFAULT_COOLDOWN_SECS: i64 = 60;
+
+static POOL: Mutex<Option<KeyPool>> = Mutex::new(None);
+/// API key entered by the user for this session. Takes precedence over the
+/// configured keys and is never persisted.
+static SESSION_API_KEY: Mutex<Option<String>> = Mutex::new(None);
+
+/// Where the API key used for a request came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum KeySource {
+    /// The `context7ApiKey` tool argument.
+    Argument,
+    /// Entered by the user when prompted.
+    Session,
+    /// A keyring entry referenced in the config.
+    Keyring,
+    /// A plain-text key in the config.
+    Config,
+}
+
+/// A configured key: plain text, or a reference to a keyring entry that is
+/// read on first use and again when the API rejects it.
+#[derive(Debug, Clone)]
+enum KeyEntry {
+    Plain(String),
+    Keyring(KeyringEntryId),
+}
+
+impl KeyEntry {
+    fn source(&self) -> KeySource {
+        match self {
+            KeyEntry::Plain(_) => KeySource::Config,
+            KeyEntry::Keyring(_) => KeySource::Keyring,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RotationMode {
+    /// Use the first available key, moving on only when it is rate limited.
+    Failover,
+    /// Spread requests across the available keys in turn.
+    RoundRobin,
+}
+
+struct PooledKey {
+    entry: KeyEntry,
+    secret: Option<String>,
+    /// Unix timestamp until which the key is skipped.
+    cooling_until: Option<i64>,
+}
+
+struct KeyPool {
+    keys: Vec<PooledKey>,
+    mode: RotationMode,
+    /// Where the next round-robin search starts.
+    next: usize,
+}
+
+/// The API key chosen for a request.
+#[derive(Debug, Clone)]
+pub(crate) struct ApiKey {
+    pub secret: String,
+    pub source: KeySource,
+    /// Position in the configured pool, for keys that came from it.
+    pool_index: Option<usize>,
+}
+
+/// Parses one entry: a `{"service", "user"}` keyring reference or a plain key.
+fn parse_entry(value: &str) -> Option<KeyEntry> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+    Some(match serde_json::from_str::<KeyringEntryId>(value) {
+        Ok(entry_id) => KeyEntry::Keyring(entry_id),
+        Err(_) => KeyEntry::Plain(value.to_string()),
+    })
+}
+
+/// Parses `CONTEXT7_API_KEYS`: a JSON array of plain keys and keyring
+/// references, or a comma-separated list of plain keys.
+fn parse_entries(value: &str) -> Vec<KeyEntry> {
+    match serde_json::from_str::<Vec<Value>>(value) {
+        Ok(items) => items
+            .into_iter()
+            .filter_map(|item| match item {
+                Value::String(key) => parse_entry(&key),
+                Value::Object(_) => serde_json::from_value(item).ok().map(KeyEntry::Keyring),
+                _ => None,
+            })
+            .collect(),
+        Err(_) => value.split(',').filter_map(parse_entry).collect(),
+    }
+}
+
+fn load_pool() -> KeyPool {
+    let keys: Vec<KeyEntry> = match settings::get("CONTEXT7_API_KEYS") {
+        Some(value) => parse_entries(&value),
+        None => settings::get("CONTEXT7_API_KEY")
+            .and_then(|value| parse_entry(&value))
+            .into_iter()
+            .collect(),
+    };
+    if keys.is_empty() {
+        log::info(
+            "api_key",
+            "Unable to resolve api key for Context7, using anonymous access",
+        )
+        .send();
+    }
+
+    KeyPool {
+        keys: keys
+            .into_iter()
+            .map(|entry| PooledKey {
+                entry,
+                secret: None,
+                cooling_until: None,
+            })
+            .collect(),
+        mode: match settings::get("KEY_ROTATION")
+            .map(|v| v.to_lowercase())
+            .as_deref()
+        {
+            Some("round-robin" | "round_robin" | "roundrobin") => RotationMode::RoundRobin,
+            _ => RotationMode::Failover,
+        },
+        next: 0,
+    }
+}
+
+fn with_pool<T>(f: impl FnOnce(&mut KeyPool) -> T) -> T {
+    let mut guard = POOL.lock().unwrap_or_else(|e| e.into_inner());
+    f(guard.get_or_insert_with(load_pool))
+}
+
+fn read_secret(entry: &KeyEntry) -> Option<String> {
+    match entry {
+        KeyEntry::Plain(key) => Some(key.clone()),
+        KeyEntry::Keyring(entry_id) => match get_keyring_secret(entry_id.clone())
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| String::from_utf8(bytes).map_err(|e| e.to_string()))
+        {
+            Ok(secret) => Some(secret.trim().to_string()).filter(|s| !s.is_empty()),
+            Err(e) => {
+                log::error(
+                    "api_key",
+                    format!("Unable to read Context7 API key from keyring: {e}"),
+                )
+                .field("service", entry_id.service.clone())
+                .field("user", entry_id.user.clone())
+                .send();
+                None
+            }
+        },
+    }
+}
+
+/// Returns the first key at or after `start` (wrapping around) that is not
+/// cooling down, skipping `exclude`.
+fn next_available(
+    cooling_until: &[Option<i64>],
+    start: usize,
+    now: i64,
+    exclude: Option<usize>,
+) -> Option<usize> {
+    let len = cooling_until.len();
+    (0..len)
+        .map(|offset| (start + offset) % len)
+        .find(|&i| Some(i) != exclude && cooling_until[i].is_none_or(|until| until <= now))
+}
+
+/// Picks a key from the pool, resolving its secret on first use. When every
+/// key is cooling down, the one that recovers first is used and the rate
+/// limiter decides whether to wait.
+fn pick(pool: &mut KeyPool, now: i64, exclude: Option<usize>, fallback: bool) -> Option<ApiKey> {
+    let start = match pool.mode {
+        RotationMode::Failover => 0,
+        RotationMode::RoundRobin => pool.next,
+    };
+    loop {
+        let cooling: Vec<Option<i64>> = pool.keys.iter().map(|k| k.cooling_until).collect();
+        let index = match next_available(&cooling, start, now, exclude) {
+            Some(index) => index,
+            None if fallback => {
+                let index = (0..pool.keys.len())
+                    .filter(|i| Some(*i) != exclude)
+                    .min_by_key(|&i| cooling[i])?;
+                return pool.keys[index].secret.clone().map(|secret| ApiKey {
+                    secret,
+                    source: pool.keys[index].entry.source(),
+                    pool_index: Some(index),
+                });
+            }
+            None => return None,
+        };
+
+        let key = &mut pool.keys[index];
+        if key.secret.is_none() {
+            key.secret = read_secret(&key.entry);
+        }
+        let Some(secret) = key.secret.clone() else {
+            key.cooling_until = Some(now + DEFAULT_COOLDOWN_SECS);
+            continue;
+        };
+        pool.next = (index + 1) % pool.keys.len();
+        return Some(ApiKey {
+            secret,
+            source: pool.keys[index].entry.source(),
+            pool_index: Some(index),
+        });
+    }
+}
+
+/// Chooses the key for a request: the tool argument, then a key entered this
+/// session, then the configured pool. `None` means anonymous access.
+pub(crate) fn select(argument: Option<&str>) -> Option<ApiKey> {
+    if let Some(secret) = argument.map(str::trim).filter(|s| !s.is_empty()) {
+        return Some(ApiKey {
+            secret: secret.to_string(),
+            source: KeySource::Argument,
+            pool_index: None,
+        });
+    }
+    if let Some(secret) = SESSION_API_KEY
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+    {
+        return Some(ApiKey {
+            secret,
+            source: KeySource::Session,
+            pool_index: None,
+        });
+    }
+    let now = Utc::now().timestamp();
+    with_pool(|pool| pick(pool, now, None, true))
+}
+
+impl ApiKey {
+    /// 1-based position of the key in the configured pool.
+    pub(crate) fn pool_position(&self) -> Option<usize> {
+        self.pool_index.map(|i| i + 1)
+    }
+}
+
+/// Names the credential a request is sent with, for per-credential rate
+/// limits. Never contains the key itself.
+pub(crate) fn credential(key: Option<&ApiKey>) -> String {
+    match key {
+        None => ratelimit::ANONYMOUS.to_string(),
+        Some(ApiKey {
+            pool_index: Some(index),
+            ..
+        }) => format!("key-{}", index + 1),
+        Some(key) => match key.source {
+            KeySource::Argument => "argument",
+            KeySource::Session => "session",
+            KeySource::Keyring | KeySource::Config => "config",
+        }
+        .to_string(),
+    }
+}
+
+/// Whether a 429 for `key` can be answered by switching keys: it came from
+/// the pool and another pooled key is not cooling down.
+pub(crate) fn can_rotate(key: &ApiKey) -> bool {
+    let Some(index) = key.pool_index else {
+        return false;
+    };
+    let now = Utc::now().timestamp();
+    with_pool(|pool| {
+        let cooling: Vec<Option<i64>> = pool.keys.iter().map(|k| k.cooling_until).collect();
+        next_available(&cooling, index, now, Some(index)).is_some()
+    })
+}
+
+/// Number of keys configured in `CONTEXT7_API_KEYS` or `CONTEXT7_API_KEY`.
+pub(crate) fn pool_size() -> usize {
+    with_pool(|pool| pool.keys.len())
+}
+
+/// Keeps a key entered by the user for the rest of the session.
+pub(crate) fn remember(secret: String) {
+    *SESSION_API_KEY.lock().unwrap_or_else(|e| e.into_inner()) = Some(secret);
+}
+
+/// Re-reads a keyring-backed key after the API rejected it, so a rotated
+/// secret is picked up without a restart. Returns the new key if it changed.
+pub(crate) fn refresh(key: &ApiKey) -> Option<ApiKey> {
+    let index = key.pool_index?;
+    with_pool(|pool| {
+        let pooled = pool.keys.get_mut(index)?;
+        let KeyEntry::Keyring(_) = pooled.entry else {
+            return None;
+        };
+        let secret = read_secret(&pooled.entry).filter(|secret| *secret != key.secret)?;
+        pooled.secret = Some(secret.clone());
+        log::info(
+            "api_key",
+            "Reloaded the Context7 API key from the keyring after it was rejected",
+        )
+        .field("keyPosition", index + 1)
+        .send();
+        Some(ApiKey {
+            secret,
+            source: KeySource::Keyring,
+            pool_index: Some(index),
+        })
+    })
+}
+
+/// Sets a pooled key aside after a 429 and returns another available key
+/// from the pool, if any.
+pub(crate) fn rotate(key: &ApiKey, retry_after: Option<Duration>) -> Option<ApiKey> {
+    let index = key.pool_index?;
+    let now = Utc::now().timestamp();
+    with_pool(|pool| {
+        let cooldown = retry_after.map_or(DEFAULT_COOLDOWN_SECS, |d| d.as_secs() as i64);
+        pool.keys.get_mut(index)?.cooling_until = Some(now + cooldown.max(1));
+        let next = pick(pool, now, Some(index), false)?;
+        log::info(
+            "api_key",
+            format!(
+                "Context7 API key {} of {} was rate limited; switching to key {}",
+                index + 1,
+                pool.keys.len(),
+                next.pool_index.map_or(0, |i| i + 1)
+            ),
+        )
+        .field("cooldownSecs", cooldown)
+        .send();
+        Some(next)
+    })
+}
+
+/// Sets the `Authorization` header for `key`, or removes it for anonymous
+/// access.
+pub(crate) fn authorize(req: &mut HttpRequest, key: Option<&ApiKey>) {
+    match key {
+        Some(key) => {
+            req.headers.insert(
+                "Authorization".to_string(),
+                format!("Bearer {}", key.secret),
+            );
+        }
+        None => {
+            req.headers.remove("Authorization");
+        }
+    }
+}
@@ -1,8 +1,9 @@
-use crate::pdk::{imports::notify_logging_message, types::*};
+use crate::{errors::ToolError, log, pdk::types::*, settings};
 use extism_pdk::config;
-use serde_json::json;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, json};
 use std::{
-    collections::hash_map::DefaultHasher,
+    collections::{HashSet, hash_map::DefaultHasher},
     fs,
     hash::{Hash, Hasher},
     path::Path,
@@ -12,20 +13,65 @@ use std::{
 
 const CACHE_DIR: &str = "/cache";
 const DEFAULT_CACHE_DAYS: u64 = 1;
+const DEFAULT_LIBRARY_INFO_CACHE_HOURS: u64 = 6;
+
+/// Tool whose entries expire after `LIBRARY_INFO_CACHE_TTL` rather than
+/// `CACHE_TTL`, since library metadata changes whenever a library is
+/// re-indexed.
+pub(crate) const LIBRARY_INFO_TOOL: &str = "get_library_info";
+
+/// Words dropped from queries during normalization; they rarely change what
+/// documentation a query should return.
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "can", "do", "does", "for", "from", "how",
+    "i", "in", "is", "it", "my", "of", "on", "or", "the", "to", "what", "when", "with",
+];
 
 static CACHE_ENABLED: OnceLock<bool> = OnceLock::new();
 static CACHE_TTL: OnceLock<Duration> = OnceLock::new();
+static LIBRARY_INFO_CACHE_TTL: OnceLock<Duration> = OnceLock::new();
+static NORMALIZE_QUERIES: OnceLock<bool> = OnceLock::new();
+static SIMILARITY_THRESHOLD: OnceLock<Option<f64>> = OnceLock::new();
+
+/// Tool arguments that can key a cache entry.
+///
+/// The query is exposed separately so it can be normalized before hashing and
+/// compared against other entries for near-match lookups.
+pub(crate) trait CacheKey: Hash {
+    fn query(&self) -> &str;
+
+    fn with_query(&self, query: String) -> Self;
+}
+
+/// On-disk cache entry: the result, with the key it was stored under kept
+/// alongside for near-match lookups.
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    #[serde(rename = "cacheKey")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    key: Option<CacheEntryKey>,
+
+    #[serde(flatten)]
+    result: CallToolResult,
+}
 
-fn is_enabled() -> bool {
+#[derive(Serialize, Deserialize)]
+struct CacheEntryKey {
+    /// Hash of every argument except the query.
+    variant: String,
+    query: String,
+}
+
+pub(crate) fn is_enabled() -> bool {
     *CACHE_ENABLED.get_or_init(|| {
         let exists = Path::new(CACHE_DIR).is_dir();
         if !exists {
-            notify_logging_message(LoggingMessageNotificationParam {
-                data: json!("Cache directory /cache is not mounted; caching is disabled"),
-                level: LoggingLevel::Info,
-                ..Default::default()
-            })
-            .ok();
+            log::info(
+                "cache",
+                "Cache directory /cache is not mounted; caching is disabled",
+            )
+            .send();
         }
         exists
     })
@@ -42,14 +88,91 @@ fn ttl() -> Duration {
     })
 }
 
-fn cache_path<T: Hash>(tool_name: &str, args: &T) -> String {
+/// TTL of `get_library_info` entries, read from `LIBRARY_INFO_CACHE_TTL` in
+/// hours.
+fn library_info_ttl() -> Duration {
+    *LIBRARY_INFO_CACHE_TTL.get_or_init(|| {
+        let hours = settings::get("LIBRARY_INFO_CACHE_TTL")
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_LIBRARY_INFO_CACHE_HOURS);
+        Duration::from_secs(hours * 60 * 60)
+    })
+}
+
+fn ttl_for(tool_name: &str) -> Duration {
+    if tool_name == LIBRARY_INFO_TOOL {
+        library_info_ttl()
+    } else {
+        ttl()
+    }
+}
+
+fn normalize_queries() -> bool {
+    *NORMALIZE_QUERIES.get_or_init(|| settings::flag("CACHE_NORMALIZE_QUERIES", false))
+}
+
+fn similarity_threshold() -> Option<f64> {
+    *SIMILARITY_THRESHOLD.get_or_init(|| {
+        settings::get("CACHE_SIMILARITY_THRESHOLD")
+            .and_then(|v| v.parse::<f64>().ok())
+            .filter(|t| *t > 0.0 && *t <= 1.0)
+    })
+}
+
+/// Case-folds the query, collapses punctuation and whitespace, and removes
+/// stopwords.
+pub(crate) fn normalize_query(query: &str) -> String {
+    query
+        .to_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '+' || c == '#' {
+                c
+            } else {
+                ' '
+            }
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .filter(|word| !STOPWORDS.contains(word))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Jaccard similarity of the word sets of two normalized queries.
+fn query_similarity(a: &str, b: &str) -> f64 {
+    let a: HashSet<&str> = a.split_whitespace().collect();
+    let b: HashSet<&str> = b.split_whitespace().collect();
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+fn hash_hex<T: Hash>(value: &T) -> String {
     let mut hasher = DefaultHasher::new();
-    args.hash(&mut hasher);
-    let hash = hasher.finish();
-    format!("{}/{}_{:x}.json", CACHE_DIR, tool_name, hash)
+    value.hash(&mut hasher);
+    format!("{:x}", hasher.finish())
+}
+
+fn entry_key<T: CacheKey>(args: &T) -> CacheEntryKey {
+    CacheEntryKey {
+        variant: hash_hex(&args.with_query(String::new())),
+        query: normalize_query(args.query()),
+    }
 }
 
-fn is_fresh(path: &str) -> bool {
+fn cache_path<T: CacheKey>(tool_name: &str, args: &T) -> String {
+    let hash = if normalize_queries() {
+        hash_hex(&args.with_query(normalize_query(args.query())))
+    } else {
+        hash_hex(args)
+    };
+    format!("{}/{}_{}.json", CACHE_DIR, tool_name, hash)
+}
+
+fn is_fresh(path: &str, ttl: Duration) -> bool {
     let Ok(metadata) = fs::metadata(path) else {
         return false;
     };
@@ -59,49 +182,120 @@ fn is_fresh(path: &str) -> bool {
     let Ok(elapsed) = SystemTime::now().duration_since(modified) else {
         return false;
     };
-    elapsed < ttl()
+    elapsed < ttl
 }
 
-pub(crate) fn get<T: Hash>(tool_name: &str, args: &T) -> Option<CallToolResult> {
-    if !is_enabled() {
+fn read_entry(path: &str, ttl: Duration) -> Option<CacheEntry> {
+    if !is_fresh(path, ttl) {
         return None;
     }
+    let data = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&data).ok()
+}
 
-    let path = cache_path(tool_name, args);
-
-    if !is_fresh(&path) {
+pub(crate) fn get<T: CacheKey>(tool_name: &str, args: &T) -> Option<CallToolResult> {
+    if !is_enabled() {
         return None;
     }
 
-    let data = fs::read_to_string(&path).ok()?;
-    let result: CallToolResult = serde_json::from_str(&data).ok()?;
+    if let Some(entry) = read_entry(&cache_path(tool_name, args), ttl_for(tool_name)) {
+        log::debug("cache", "Cache hit")
+            .field("tool", tool_name)
+            .field("cache", "hit")
+            .send();
+        return Some(entry.result);
+    }
+
+    let result =
+        similarity_threshold().and_then(|threshold| get_similar(tool_name, args, threshold));
+    log::debug(
+        "cache",
+        if result.is_some() {
+            "Cache near-match hit"
+        } else {
+            "Cache miss"
+        },
+    )
+    .field("tool", tool_name)
+    .field(
+        "cache",
+        if result.is_some() {
+            "nearMatch"
+        } else {
+            "miss"
+        },
+    )
+    .send();
+    result
+}
+
+/// Finds the fresh entry for the same tool and non-query arguments whose
+/// normalized query is most similar to `args`, marking it as a near-match.
+fn get_similar<T: CacheKey>(tool_name: &str, args: &T, threshold: f64) -> Option<CallToolResult> {
+    let key = entry_key(args);
+    let prefix = format!("{}_", tool_name);
+
+    let (similarity, cached_query, mut result) = fs::read_dir(CACHE_DIR)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension().and_then(|e| e.to_str()) == Some("json")
+                && path
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with(&prefix))
+        })
+        .filter_map(|path| read_entry(path.to_str()?, ttl_for(tool_name)))
+        .filter_map(|entry| {
+            let cached = entry.key?;
+            if cached.variant != key.variant {
+                return None;
+            }
+            let similarity = query_similarity(&key.query, &cached.query);
+            (similarity >= threshold).then_some((similarity, cached.query, entry.result))
+        })
+        .max_by(|a, b| a.0.total_cmp(&b.0))?;
+
+    result.meta.get_or_insert_with(Map::new).insert(
+        "nearMatch".to_string(),
+        json!({ "cachedQuery": cached_query, "similarity": similarity }),
+    );
+    result.content.insert(
+        0,
+        ContentBlock::Text(TextContent {
+            text: format!(
+                "Near-match cached result for the similar query \"{}\" (similarity {:.2}).",
+                cached_query, similarity
+            ),
+            ..Default::default()
+        }),
+    );
     Some(result)
 }
 
-pub(crate) fn put<T: Hash>(tool_name: &str, args: &T, result: &CallToolResult) {
+pub(crate) fn put<T: CacheKey>(tool_name: &str, args: &T, result: &CallToolResult) {
     if !is_enabled() {
         return;
     }
 
     let path = cache_path(tool_name, args);
+    let entry = CacheEntry {
+        key: Some(entry_key(args)),
+        result: result.clone(),
+    };
 
-    let Ok(data) = serde_json::to_string(result) else {
-        notify_logging_message(LoggingMessageNotificationParam {
-            data: json!("Failed to serialize cache entry"),
-            level: LoggingLevel::Warning,
-            ..Default::default()
-        })
-        .ok();
+    let Ok(data) = serde_json::to_string(&entry) else {
+        log::warning("cache", "Failed to serialize cache entry")
+            .field("tool", tool_name)
+            .send();
         return;
     };
 
     if let Err(e) = fs::write(&path, data) {
-        notify_logging_message(LoggingMessageNotificationParam {
-            data: json!(format!("Failed to write cache file {}: {}", path, e)),
-            level: LoggingLevel::Warning,
-            ..Default::default()
-        })
-        .ok();
+        log::warning("cache", format!("Failed to write cache file {path}: {e}"))
+            .field("tool", tool_name)
+            .send();
     }
 }
 
@@ -119,7 +313,7 @@ pub(crate) fn clear() -> CallToolResult {
     let entries = match fs::read_dir(CACHE_DIR) {
         Ok(entries) => entries,
         Err(e) => {
-            return CallToolResult::error(format!("Failed to read cache directory: {}", e));
+            return ToolError::internal(format!("Failed to read cache directory: {e}")).into();
         }
     };
 
@@ -148,10 +342,11 @@ pub(crate) fn clear() -> CallToolResult {
             ..Default::default()
         }
     } else {
-        CallToolResult::error(format!(
+        ToolError::internal(format!(
             "Failed to remove {} cache entries: {}",
             errors.len(),
             errors.join("; ")
         ))
+        .into()
     }
 }
@@ -0,0 +1,186 @@
+use crate::{
+    log,
+    pdk::{imports::create_elicitation, types::*},
+    settings,
+    types::*,
+};
+use std::{
+    collections::HashMap,
+    sync::{
+        OnceLock,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+const API_KEY_FIELD: &str = "apiKey";
+const LIBRARY_FIELD: &str = "library";
+const REMEMBER_FIELD: &str = "remember";
+/// How long the user has to answer a prompt, in milliseconds.
+const PROMPT_TIMEOUT_MS: i64 = 5 * 60 * 1000;
+/// Longest library description shown in a choice.
+const MAX_DESCRIPTION_CHARS: usize = 80;
+
+static ELICIT_API_KEY: OnceLock<bool> = OnceLock::new();
+static DISAMBIGUATE_LIBRARIES: OnceLock<bool> = OnceLock::new();
+/// Set once the user declines or the client cannot elicit, so the prompt is
+/// not repeated on every request.
+static API_KEY_DECLINED: AtomicBool = AtomicBool::new(false);
+
+/// An API key entered by the user.
+pub(crate) struct PromptedApiKey {
+    pub key: String,
+    /// Whether to keep using the key for the rest of the session.
+    pub remember: bool,
+}
+
+fn api_key_prompt_enabled() -> bool {
+    *ELICIT_API_KEY.get_or_init(|| settings::flag("ELICIT_API_KEY", true))
+}
+
+/// Whether `resolve_library_id` asks the user to pick between close matches.
+pub(crate) fn disambiguation_enabled() -> bool {
+    *DISAMBIGUATE_LIBRARIES.get_or_init(|| settings::flag("DISAMBIGUATE_LIBRARIES", false))
+}
+
+fn text_field(
+    content: &HashMap<String, ElicitationResultContentValue>,
+    name: &str,
+) -> Option<String> {
+    match content.get(name)? {
+        ElicitationResultContentValue::String(value) => {
+            Some(value.trim().to_string()).filter(|v| !v.is_empty())
+        }
+        _ => None,
+    }
+}
+
+fn bool_field(
+    content: &HashMap<String, ElicitationResultContentValue>,
+    name: &str,
+) -> Option<bool> {
+    match content.get(name)? {
+        ElicitationResultContentValue::Bool(value) => Some(*value),
+        _ => None,
+    }
+}
+
+/// Asks the user for a Context7 API key, explaining `reason`. Returns `None`
+/// when prompting is disabled, the client does not support elicitation, or
+/// the user declines; after that the user is not asked again this session.
+pub(crate) fn request_api_key(reason: &str) -> Option<PromptedApiKey> {
+    if !api_key_prompt_enabled() || API_KEY_DECLINED.load(Ordering::Relaxed) {
+        return None;
+    }
+
+    let request = ElicitationRequestParamWithTimeout {
+        inner: ElicitationRequestParam::Form {
+            message: format!(
+                "{reason} Enter a Context7 API key (from https://context7.com/dashboard) to \
+                 continue. The key is kept in memory only and never written to the cache."
+            ),
+            requested_schema: Schema {
+                properties: HashMap::from([
+                    (
+                        API_KEY_FIELD.to_string(),
+                        PrimitiveSchemaDefinition::String(StringSchema {
+                            title: Some("Context7 API key".to_string()),
+                            min_length: Some(1),
+                            ..Default::default()
+                        }),
+                    ),
+                    (
+                        REMEMBER_FIELD.to_string(),
+                        PrimitiveSchemaDefinition::Boolean(BooleanSchema {
+                            title: Some("Use for the rest of this session".to_string()),
+                            description: Some(
+                                "If unchecked, the key is only used to retry this request."
+                                    .to_string(),
+                            ),
+                            default: Some(true),
+                        }),
+                    ),
+                ]),
+                required: Some(vec![API_KEY_FIELD.to_string()]),
+            },
+        },
+        timeout: Some(PROMPT_TIMEOUT_MS),
+    };
+
+    let prompted = match create_elicitation(request) {
+        Ok(ElicitationResult {
+            action: ElicitationResultAction::Accept,
+            content: Some(content),
+        }) => text_field(&content, API_KEY_FIELD).map(|key| PromptedApiKey {
+            key,
+            remember: bool_field(&content, REMEMBER_FIELD).unwrap_or(true),
+        }),
+        Ok(_) => None,
+        Err(e) => {
+            log::info(
+                "elicitation",
+                format!("Unable to prompt for a Context7 API key: {e}"),
+            )
+            .send();
+            None
+        }
+    };
+
+    if prompted.is_none() {
+        API_KEY_DECLINED.store(true, Ordering::Relaxed);
+    }
+    prompted
+}
+
+fn choice_label(library: &Library) -> String {
+    let description = library.description.trim();
+    if description.is_empty() {
+        return format!("{} ({})", library.title, library.id);
+    }
+    let mut short: String = description.chars().take(MAX_DESCRIPTION_CHARS).collect();
+    if short.len() < description.len() {
+        short.push('…');
+    }
+    format!("{} ({}) – {short}", library.title, library.id)
+}
+
+/// Asks the user which of `candidates` they mean, returning the chosen
+/// library ID. Returns `None` if the user declines or the client does not
+/// support elicitation.
+pub(crate) fn choose_library(library_name: &str, candidates: &[&Library]) -> Option<String> {
+    let request = ElicitationRequestParamWithTimeout {
+        inner: ElicitationRequestParam::Form {
+            message: format!("Several libraries match '{library_name}'. Which one do you mean?"),
+            requested_schema: Schema {
+                properties: HashMap::from([(
+                    LIBRARY_FIELD.to_string(),
+                    PrimitiveSchemaDefinition::Enum(EnumSchema {
+                        title: Some("Library".to_string()),
+                        r#enum: candidates.iter().map(|l| l.id.clone()).collect(),
+                        enum_names: Some(candidates.iter().map(|l| choice_label(l)).collect()),
+                        ..Default::default()
+                    }),
+                )]),
+                required: Some(vec![LIBRARY_FIELD.to_string()]),
+            },
+        },
+        timeout: Some(PROMPT_TIMEOUT_MS),
+    };
+
+    match create_elicitation(request) {
+        Ok(ElicitationResult {
+            action: ElicitationResultAction::Accept,
+            content: Some(content),
+        }) => {
+            text_field(&content, LIBRARY_FIELD).filter(|id| candidates.iter().any(|l| &l.id == id))
+        }
+        Ok(_) => None,
+        Err(e) => {
+            log::info(
+                "elicitation",
+                format!("Unable to ask which library was meant: {e}"),
+            )
+            .send();
+            None
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use crate::pdk::types::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Map;
+use std::fmt;
+
+/// What went wrong, so callers can branch on the kind instead of matching
+/// message text.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub(crate) enum ErrorKind {
+    /// The tool arguments are missing, malformed or out of range.
+    InvalidArguments,
+    /// The library or documentation does not exist.
+    NotFound,
+    /// The API key is missing, invalid or lacks access.
+    Unauthorized,
+    /// The request was rate limited. `retryAfter` is in seconds, when known.
+    RateLimited {
+        #[serde(rename = "retryAfter")]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(default)]
+        retry_after: Option<u64>,
+    },
+    /// The API returned an unexpected status.
+    Upstream { status: u16 },
+    /// The API could not be reached.
+    Network,
+    /// The request was blocked by the library policy or query redaction.
+    PolicyDenied,
+    /// A response could not be parsed or converted.
+    Deserialization,
+    /// A local failure, such as a cache I/O error.
+    Internal,
+}
+
+/// A tool failure with its kind and a human-readable message. Returned as the
+/// text content of an error result and as `_meta.error`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct ToolError {
+    #[serde(flatten)]
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl fmt::Display for ToolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl ToolError {
+    pub(crate) fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn invalid_arguments(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::InvalidArguments, message)
+    }
+
+    pub(crate) fn policy_denied(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::PolicyDenied, message)
+    }
+
+    pub(crate) fn network(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Network, message)
+    }
+
+    pub(crate) fn deserialization(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Deserialization, message)
+    }
+
+    pub(crate) fn internal(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Internal, message)
+    }
+
+    /// Classifies a non-success HTTP status from the Context7 API.
+    pub(crate) fn from_status(
+        status: u16,
+        retry_after: Option<u64>,
+        message: impl Into<String>,
+    ) -> Self {
+        let kind = match status {
+            400 | 422 => ErrorKind::InvalidArguments,
+            401 | 403 => ErrorKind::Unauthorized,
+            404 => ErrorKind::NotFound,
+            429 => ErrorKind::RateLimited { retry_after },
+            _ => ErrorKind::Upstream { status },
+        };
+        Self::new(kind, message)
+    }
+
+    /// Recovers the error from a result converted from a `ToolError`.
+    pub(crate) fn from_result(result: &CallToolResult) -> Option<Self> {
+        let error = result.meta.as_ref()?.get("error")?;
+        serde_json::from_value(error.clone()).ok()
+    }
+}
+
+/// Builds an error `CallToolResult` carrying the message as text and the typed
+/// error as `_meta.error`. Structured content is left unset, since the error
+/// does not match the tool's output schema.
+impl From<ToolError> for CallToolResult {
+    fn from(error: ToolError) -> Self {
+        let mut result = CallToolResult::error(error.message.clone());
+        if let Ok(value) = serde_json::to_value(&error) {
+            result
+                .meta
+                .get_or_insert_with(Map::new)
+                .insert("error".to_string(), value);
+        }
+        result
+    }
+}
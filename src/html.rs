@@ -0,0 +1,167 @@
+use crate::{settings, types::*};
+use htmd::{
+    HtmlToMarkdown,
+    options::{BulletListMarker, Options},
+};
+use std::sync::OnceLock;
+use url::Url;
+
+/// Tags whose presence marks a field as HTML rather than Markdown that merely
+/// mentions angle brackets (e.g. `Vec<String>`).
+const HTML_TAGS: &[&str] = &[
+    "a",
+    "b",
+    "blockquote",
+    "br",
+    "code",
+    "div",
+    "em",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "i",
+    "img",
+    "li",
+    "ol",
+    "p",
+    "pre",
+    "span",
+    "strong",
+    "table",
+    "td",
+    "th",
+    "tr",
+    "ul",
+];
+
+/// Query parameters added by analytics and newsletter tooling.
+const TRACKING_PARAMS: &[&str] = &["fbclid", "gclid", "mc_cid", "mc_eid", "ref_src", "msclkid"];
+
+/// Link texts used for heading permalinks.
+const ANCHOR_TEXTS: &[&str] = &["", "#", "¶", "§", "🔗", "link"];
+
+static HTML_TO_MARKDOWN: OnceLock<bool> = OnceLock::new();
+
+/// Whether the `HTML_TO_MARKDOWN` post-processing pass is enabled.
+pub(crate) fn is_enabled() -> bool {
+    *HTML_TO_MARKDOWN.get_or_init(|| settings::flag("HTML_TO_MARKDOWN", false))
+}
+
+/// Returns true when `text` contains at least one opening or closing tag from
+/// [`HTML_TAGS`].
+fn looks_like_html(text: &str) -> bool {
+    text.match_indices('<').any(|(i, _)| {
+        let rest = text[i + 1..].trim_start_matches('/');
+        let name: String = rest
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        let after = rest[name.len()..].chars().next();
+        HTML_TAGS.contains(&name.as_str()) && matches!(after, Some('>' | ' ' | '/' | '\n' | '\t'))
+    })
+}
+
+fn strip_tracking(url: &str) -> String {
+    let Ok(mut parsed) = Url::parse(url) else {
+        return url.to_string();
+    };
+    if parsed.query().is_none() {
+        return url.to_string();
+    }
+    let kept: Vec<(String, String)> = parsed
+        .query_pairs()
+        .filter(|(k, _)| !k.starts_with("utm_") && !TRACKING_PARAMS.contains(&k.as_ref()))
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect();
+    if kept.is_empty() {
+        parsed.set_query(None);
+    } else {
+        parsed.query_pairs_mut().clear().extend_pairs(kept);
+    }
+    parsed.to_string()
+}
+
+/// Parses a Markdown inline link at the start of `s`, returning its text, URL
+/// and byte length.
+fn parse_link(s: &str) -> Option<(&str, &str, usize)> {
+    let text_end = s.find("](")?;
+    let text = &s[1..text_end];
+    if text.contains(['[', ']', '\n']) {
+        return None;
+    }
+    let url_start = text_end + 2;
+    let url_len = s[url_start..].find(')')?;
+    let url = &s[url_start..url_start + url_len];
+    if url.contains(char::is_whitespace) {
+        return None;
+    }
+    Some((text, url, url_start + url_len + 1))
+}
+
+/// Drops heading permalinks (`[#](#section)`) and removes tracking parameters
+/// from the remaining links.
+fn strip_link_noise(markdown: &str) -> String {
+    let mut out = String::with_capacity(markdown.len());
+    let mut rest = markdown;
+
+    while let Some(start) = rest.find('[') {
+        out.push_str(&rest[..start]);
+        let candidate = &rest[start..];
+        match parse_link(candidate) {
+            Some((text, url, len)) => {
+                let is_anchor = url.starts_with('#') && ANCHOR_TEXTS.contains(&text.trim());
+                if !is_anchor {
+                    out.push('[');
+                    out.push_str(text);
+                    out.push_str("](");
+                    out.push_str(&strip_tracking(url));
+                    out.push(')');
+                }
+                rest = &candidate[len..];
+            }
+            None => {
+                out.push('[');
+                rest = &candidate[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+
+    out.replace('¶', "").trim().to_string()
+}
+
+/// Converts an HTML fragment to Markdown, returning `None` if conversion fails.
+fn html_to_markdown(html: &str) -> Option<String> {
+    let converter = HtmlToMarkdown::builder()
+        .options(Options {
+            bullet_list_marker: BulletListMarker::Dash,
+            ..Default::default()
+        })
+        .skip_tags(vec!["script", "style", "noscript", "svg", "button"])
+        .build();
+    converter.convert(html).ok().map(|md| strip_link_noise(&md))
+}
+
+fn normalize_field(field: &mut String) {
+    if looks_like_html(field)
+        && let Some(markdown) = html_to_markdown(field)
+    {
+        *field = markdown;
+    }
+}
+
+/// Converts HTML in info snippet content and code descriptions to Markdown.
+/// Code in `CodeListEntry::code` is left untouched.
+pub(crate) fn normalize_response(response: &mut QueryDocsResponse) {
+    for snippet in &mut response.code_snippets {
+        normalize_field(&mut snippet.code_description);
+    }
+    for snippet in &mut response.info_snippets {
+        normalize_field(&mut snippet.content);
+    }
+}
@@ -1,79 +1,84 @@
+mod aliases;
+mod answer;
+mod api_key;
 mod cache;
+mod elicitation;
+mod errors;
+mod html;
+mod library_id;
+mod log;
+mod meta;
 mod pdk;
+mod policy;
+mod rank;
+mod ratelimit;
+mod redact;
+mod render;
+mod resolve;
+mod settings;
+mod snippets;
 mod types;
 
 use crate::{
-    pdk::imports::{get_keyring_secret, notify_logging_message},
+    api_key::KeySource,
+    errors::{ErrorKind, ToolError},
     types::*,
 };
 use anyhow::Result;
-use chrono::{DateTime, Utc};
+use chrono::Utc;
 use extism_pdk::*;
 use pdk::types::*;
 use schemars::schema_for;
-use serde_json::{Map, Value, json};
-use std::sync::OnceLock;
+use serde_json::{Map, Value};
 use std::thread;
 use std::time::Duration;
 use url::Url;
 
 const CONTEXT7_API_BASE_URL: &str = "https://context7.com/api";
-static CONTEXT7_API_KEY: OnceLock<Option<String>> = OnceLock::new();
-
-fn resolve_context7_api_key() -> Option<String> {
-    let api_key = match config::get("CONTEXT7_API_KEY") {
-        Ok(Some(item)) => match serde_json::from_str::<KeyringEntryId>(item.as_str()) {
-            Ok(entry_id) => match get_keyring_secret(entry_id) {
-                Ok(secret_bytes) => match String::from_utf8(secret_bytes) {
-                    Ok(secret_str) => Some(secret_str),
-                    Err(e) => {
-                        notify_logging_message(LoggingMessageNotificationParam {
-                            data: json!(e.to_string()),
-                            level: LoggingLevel::Error,
-
-                            ..Default::default()
-                        })
-                        .ok();
-                        None
-                    }
-                },
-                Err(_) => None,
-            },
-            Err(_) => Some(item),
-        },
-        Ok(None) => None,
-        Err(e) => {
-            notify_logging_message(LoggingMessageNotificationParam {
-                data: json!(e.to_string()),
-                level: LoggingLevel::Error,
-
-                ..Default::default()
-            })
-            .ok();
-            None
-        }
-    };
-    if api_key.is_none() {
-        notify_logging_message(LoggingMessageNotificationParam {
-            data: json!(
-                "Unable to resolve api key for Context7, using anonymous access".to_string()
-            ),
-            level: LoggingLevel::Info,
-
-            ..Default::default()
-        })
-        .ok();
-    }
-    api_key
-}
 
 pub(crate) fn call_tool(input: CallToolRequest) -> Result<CallToolResult> {
-    Ok(match input.request.name.as_str() {
+    let tool = input.request.name.clone();
+    let library_id = input
+        .request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("libraryId").cloned());
+    let started = Utc::now();
+    log::debug("tools", "Tool called")
+        .field("tool", tool.as_str())
+        .send();
+
+    let result = match input.request.name.as_str() {
         "resolve_library_id" => resolve_library_id(input),
+        "get_library_info" => get_library_info(input),
         "query_docs" => query_docs(input),
+        "query_docs_multi" => query_docs_multi(input),
+        "answer_from_docs" => answer_from_docs(input),
         "clear_cache" => cache::clear(),
-        _ => CallToolResult::error(format!("Unknown tool: {}", input.request.name)),
-    })
+        "context7_status" => context7_status(input),
+        _ => ToolError::invalid_arguments(format!("Unknown tool: {}", input.request.name)).into(),
+    };
+
+    let message = if result.is_error == Some(true) {
+        "Tool failed"
+    } else {
+        "Tool completed"
+    };
+    let mut event = log::debug("tools", message)
+        .field("tool", tool)
+        .field("latencyMs", (Utc::now() - started).num_milliseconds());
+    if let Some(library_id) = library_id {
+        event = event.field("libraryId", library_id);
+    }
+    if let Some(error) = ToolError::from_result(&result) {
+        let kind = serde_json::to_value(&error.kind)
+            .ok()
+            .and_then(|kind| kind.get("kind").cloned())
+            .unwrap_or_default();
+        event = event.field("error", error.message).field("kind", kind);
+    }
+    event.send();
+    Ok(result)
 }
 
 pub(crate) fn list_tools(_input: ListToolsRequest) -> Result<ListToolsResult> {
@@ -97,6 +102,40 @@ pub(crate) fn list_tools(_input: ListToolsRequest) -> Result<ListToolsResult> {
               output_schema: Some(schema_for!(QueryDocsResponse)),
               title: Some("Query Documentation".to_string()),
             },
+            Tool {
+                name: "query_docs_multi".to_string(),
+                annotations: Some(ToolAnnotations {
+                    read_only_hint: Some(true),
+
+                    ..Default::default()
+                }),
+                description: Some(
+                    r#"Queries up-to-date documentation from Context7 for several libraries at once, for questions that span multiple libraries (e.g. "axum with sqlx and tokio").
+
+                    Provide the Context7-compatible library IDs obtained from 'resolve_library_id' (or given by the user in the format '/org/project' or '/org/project/version') and a single query. Results are grouped by library. An optional token budget is shared across the libraries in proportion to how much documentation each returned. A failure for one library does not fail the others.
+
+                    IMPORTANT: Do not call this tool more than 3 times per question. If you cannot find what you need after 3 calls, use the best information you have."#.to_string(),
+                ),
+                input_schema: schema_for!(QueryDocsMultiArguments),
+                output_schema: Some(schema_for!(QueryDocsMultiResponse)),
+                title: Some("Query Documentation for Multiple Libraries".to_string()),
+            },
+            Tool {
+                name: "answer_from_docs".to_string(),
+                annotations: Some(ToolAnnotations {
+                    read_only_hint: Some(true),
+
+                    ..Default::default()
+                }),
+                description: Some(
+                    r#"Answers a question from a library's up-to-date Context7 documentation. The documentation is fetched as in 'query_docs', then the client's model is asked (via sampling) to write a short answer grounded only in the returned snippets, citing their IDs.
+
+                    Use this instead of 'query_docs' when a concise answer is enough and the full snippets are not needed. Requires a client that supports sampling. You must call 'resolve_library_id' first to obtain the library ID, UNLESS the user explicitly provides a library ID in the format '/org/project' or '/org/project/version'."#.to_string(),
+                ),
+                input_schema: schema_for!(AnswerFromDocsArguments),
+                output_schema: Some(schema_for!(AnswerFromDocsResponse)),
+                title: Some("Answer from Documentation".to_string()),
+            },
             Tool {
                 name: "resolve_library_id".to_string(),
                 annotations: Some(ToolAnnotations{
@@ -132,6 +171,22 @@ pub(crate) fn list_tools(_input: ListToolsRequest) -> Result<ListToolsResult> {
                 output_schema: Some(schema_for!(ResolveLibraryIdResponse)),
                 title: Some("Resolve Context7 Library ID".to_string()),
             },
+            Tool {
+                name: "get_library_info".to_string(),
+                annotations: Some(ToolAnnotations {
+                    read_only_hint: Some(true),
+
+                    ..Default::default()
+                }),
+                description: Some(
+                    r#"Returns the Context7 record for a single library ID: its title, description, versions, branch, last update date, indexing state, snippet count and trust score.
+
+                    Use this instead of 'resolve_library_id' when the library ID is already known (from the user, a lockfile or an earlier call) and only its details are needed."#.to_string(),
+                ),
+                input_schema: schema_for!(GetLibraryInfoArguments),
+                output_schema: Some(schema_for!(Library)),
+                title: Some("Get Library Info".to_string()),
+            },
             Tool {
                 name: "clear_cache".to_string(),
                 annotations: Some(ToolAnnotations {
@@ -147,16 +202,30 @@ pub(crate) fn list_tools(_input: ListToolsRequest) -> Result<ListToolsResult> {
                 output_schema: None,
                 title: Some("Clear Cache".to_string()),
             },
+            Tool {
+                name: "context7_status".to_string(),
+                annotations: Some(ToolAnnotations {
+                    read_only_hint: Some(true),
+
+                    ..Default::default()
+                }),
+                description: Some(
+                    "Checks the Context7 connection: whether an API key was resolved and where from, whether the API accepts it, and the current rate-limit headers. The key itself is never returned.".to_string(),
+                ),
+                input_schema: schema_for!(StatusArguments),
+                output_schema: Some(schema_for!(StatusResponse)),
+                title: Some("Context7 Status".to_string()),
+            },
         ],
     })
 }
 
 trait Context7Headers {
-    fn insert_context7_headers(self, context7_api_key: Option<&str>) -> Self;
+    fn insert_context7_headers(self) -> Self;
 }
 
 impl Context7Headers for HttpRequest {
-    fn insert_context7_headers(mut self, context7_api_key: Option<&str>) -> Self {
+    fn insert_context7_headers(mut self) -> Self {
         self.headers.insert(
             "X-Context7-Source".to_string(),
             "hyper-mcp/context7-plugin".to_string(),
@@ -165,47 +234,22 @@ impl Context7Headers for HttpRequest {
             "X-Context7-Server-Version".to_string(),
             env!("CARGO_PKG_VERSION").to_string(),
         );
-        if let Some(api_key) = context7_api_key.map(Some).unwrap_or_else(|| {
-            CONTEXT7_API_KEY
-                .get_or_init(resolve_context7_api_key)
-                .as_deref()
-        }) {
-            self.headers
-                .insert("Authorization".to_string(), format!("Bearer {api_key}"));
-        }
         self
     }
 }
 
-fn http_request_with_retry(req: &HttpRequest) -> Result<HttpResponse> {
-    fn parse_retry_after(value: &str) -> Option<Duration> {
-        if let Ok(secs) = value.parse::<u64>() {
-            Some(Duration::from_secs(secs))
-        } else if let Ok(date) = DateTime::parse_from_rfc2822(value) {
-            let target = date.with_timezone(&Utc);
-            let now = Utc::now();
-            if target > now {
-                let delta = target - now;
-                delta.to_std().ok()
-            } else {
-                None
-            }
-        } else if let Ok(date) =
-            chrono::NaiveDateTime::parse_from_str(value, "%a %b %e %H:%M:%S %Y")
-        {
-            let target = date.and_utc();
-            let now = Utc::now();
-            if target > now {
-                let delta = target - now;
-                delta.to_std().ok()
-            } else {
-                None
-            }
-        } else {
-            None
-        }
-    }
-
+/// Sends a request, retrying network errors and 5xx responses. Rate limits
+/// are checked before every attempt, so a 429 is followed by a short wait or a
+/// fast `rateLimited` error rather than further attempts. With `rotatable` set,
+/// a 429 or a client-side hold is returned straight away so the caller can
+/// switch to another pooled key instead. Rate limits are tracked per
+/// `credential` (see [`api_key::credential`]).
+fn send_with_retry(
+    req: &HttpRequest,
+    label: &str,
+    credential: &str,
+    rotatable: bool,
+) -> Result<HttpResponse, ToolError> {
     const MAX_HTTP_ATTEMPTS: u32 = 3;
     const RETRY_DELAY: Duration = Duration::from_secs(15);
 
@@ -213,209 +257,964 @@ fn http_request_with_retry(req: &HttpRequest) -> Result<HttpResponse> {
 
     loop {
         attempt += 1;
-        match http::request::<()>(req, None) {
+        ratelimit::before_request(credential, !rotatable)?;
+        if log::enabled(LoggingLevel::Debug) {
+            log::debug("http", format!("{label} request"))
+                .field("method", req.method.clone().unwrap_or_default())
+                .field("url", req.url.as_str())
+                .field("headers", log::headers(&req.headers))
+                .field("attempt", attempt)
+                .send();
+        }
+        let started = Utc::now();
+        let response = http::request::<()>(req, None);
+        let latency = (Utc::now() - started).num_milliseconds();
+        match response {
             Ok(res) => {
+                ratelimit::record(credential, &res);
                 let status = res.status_code();
+                meta::record_attempt(Some(status), latency);
+                log::debug("http", format!("{label} response"))
+                    .field("status", status)
+                    .field("attempt", attempt)
+                    .field("latencyMs", latency)
+                    .send();
 
-                if attempt < MAX_HTTP_ATTEMPTS && (status == 429 || status >= 500) {
-                    thread::sleep(
-                        res.header("retry-after")
-                            .or_else(|| res.header("Retry-After"))
-                            .and_then(parse_retry_after)
-                            .unwrap_or(RETRY_DELAY),
-                    );
+                if attempt < MAX_HTTP_ATTEMPTS && status == 429 && !rotatable {
+                    continue;
+                }
+                if attempt < MAX_HTTP_ATTEMPTS && status >= 500 {
+                    thread::sleep(ratelimit::retry_after(&res).unwrap_or(RETRY_DELAY));
                     continue;
                 }
                 break Ok(res);
             }
             Err(e) => {
+                meta::record_attempt(None, latency);
+                log::warning("http", format!("{label} request failed: {e}"))
+                    .field("attempt", attempt)
+                    .field("latencyMs", latency)
+                    .send();
                 if attempt < MAX_HTTP_ATTEMPTS {
                     thread::sleep(RETRY_DELAY);
                     continue;
                 }
-                break Err(e);
+                break Err(ToolError::network(format!("{label} request failed: {e}")));
             }
         }
     }
 }
 
+/// Authorizes a request with the key chosen by [`api_key::select`] and sends
+/// it with [`send_with_retry`]:
+/// - On 401/403 a keyring-backed key is re-read and, if it changed, retried.
+/// - On 429, or when the key is held by its own rate limit, a pooled key is
+///   set aside and the request retried with the next available key.
+/// - If the request is still rejected, or rate limited without an API key,
+///   the user is asked for a key once and the request is retried with it.
+fn http_request_with_retry(
+    req: &HttpRequest,
+    context7_api_key: Option<&str>,
+    label: &str,
+) -> Result<HttpResponse, ToolError> {
+    let mut req = req.clone();
+    let mut key = api_key::select(context7_api_key);
+    let mut refreshed = false;
+
+    let result = loop {
+        api_key::authorize(&mut req, key.as_ref());
+        let rotatable = key.as_ref().is_some_and(api_key::can_rotate);
+        let result = send_with_retry(&req, label, &api_key::credential(key.as_ref()), rotatable);
+        let Some(current) = &key else {
+            break result;
+        };
+        let next = match &result {
+            Ok(res) if matches!(res.status_code(), 401 | 403) && !refreshed => {
+                refreshed = true;
+                api_key::refresh(current)
+            }
+            Ok(res) if res.status_code() == 429 => {
+                api_key::rotate(current, ratelimit::retry_after(res))
+            }
+            Err(ToolError {
+                kind: ErrorKind::RateLimited { retry_after },
+                ..
+            }) if rotatable => api_key::rotate(current, retry_after.map(Duration::from_secs)),
+            _ => None,
+        };
+        match next {
+            Some(next) => key = Some(next),
+            None => break result,
+        }
+    };
+
+    let anonymous = key.is_none();
+    let reason = match &result {
+        Ok(res) if matches!(res.status_code(), 401 | 403) => {
+            format!("Context7 rejected the request ({}).", res.status_code())
+        }
+        Ok(res) if anonymous && res.status_code() == 429 => {
+            "Context7 rate limited anonymous access.".to_string()
+        }
+        Err(ToolError {
+            kind: ErrorKind::RateLimited { .. },
+            ..
+        }) if anonymous => "Context7 rate limited anonymous access.".to_string(),
+        _ => return result,
+    };
+    let Some(prompted) = elicitation::request_api_key(&reason) else {
+        return result;
+    };
+
+    req.headers.insert(
+        "Authorization".to_string(),
+        format!("Bearer {}", prompted.key),
+    );
+    let result = send_with_retry(&req, label, "session", false);
+    // Only a key the API accepted may override the configured ones
+    if prompted.remember
+        && let Ok(res) = &result
+        && (200..300).contains(&res.status_code())
+    {
+        api_key::remember(prompted.key);
+    }
+    result
+}
+
+/// Returns the body of a successful API response, or an error classified by
+/// its status.
+fn response_body(res: &HttpResponse, label: &str) -> Result<String, ToolError> {
+    let body = String::from_utf8_lossy(&res.body()).to_string();
+    let status = res.status_code();
+    if !(200..300).contains(&status) {
+        return Err(ToolError::from_status(
+            status,
+            ratelimit::retry_after(res).map(|d| d.as_secs()),
+            format!("{label} request failed with status {status}: {body}"),
+        ));
+    }
+    Ok(body)
+}
+
+/// Fetches `/v2/context` in the given API format (`txt` or `json`), returning
+/// the response body.
+fn fetch_context(
+    base_url: &Url,
+    format: &str,
+    context7_api_key: Option<&str>,
+) -> Result<String, ToolError> {
+    let label = if format == "txt" {
+        "Text API"
+    } else {
+        "JSON API"
+    };
+
+    let mut url = base_url.clone();
+    url.query_pairs_mut().append_pair("type", format);
+
+    let req = HttpRequest::new(url.as_str())
+        .with_method("GET")
+        .insert_context7_headers();
+
+    let res = http_request_with_retry(&req, context7_api_key, label)?;
+
+    response_body(&res, label)
+}
+
 fn query_docs(input: CallToolRequest) -> CallToolResult {
     let mut args: QueryDocsArguments =
         match serde_json::from_value(Value::Object(input.request.arguments.unwrap_or_default())) {
             Ok(args) => args,
-            Err(e) => return CallToolResult::error(format!("Invalid arguments: {e}")),
+            Err(e) => {
+                return ToolError::invalid_arguments(format!("Invalid arguments: {e}")).into();
+            }
         };
 
-    if args.r#type.is_none() {
-        args.r#type = Some(QueryDocsType::Json);
+    if let Err(e) = redact::apply("query", &mut args.query) {
+        return ToolError::policy_denied(e).into();
+    }
+
+    fetch_docs(args)
+}
+
+/// Runs a `query_docs` request, going through the cache.
+fn fetch_docs(mut args: QueryDocsArguments) -> CallToolResult {
+    meta::reset();
+    let docs_type = args.r#type.get_or_insert(QueryDocsType::Json).clone();
+
+    match library_id::parse(&args.library_id) {
+        Ok(library_id) => args.library_id = library_id.to_string(),
+        Err(e) => return ToolError::invalid_arguments(e).into(),
     }
 
-    if let Some(cached) = cache::get("query_docs", &args) {
+    if let Err(e) = check_library_policy(&args.library_id, args.context7_api_key.as_deref()) {
+        return e.into();
+    }
+
+    if let Some(mut cached) = cache::get("query_docs", &args) {
+        meta::mark_cached(&mut cached, Some(&args.library_id));
         return cached;
     }
 
     let mut base_url = match Url::parse(&format!("{}/v2/context", CONTEXT7_API_BASE_URL)) {
         Ok(url) => url,
-        Err(e) => {
-            return CallToolResult::error(e.to_string());
-        }
+        Err(e) => return ToolError::internal(e.to_string()).into(),
     };
     base_url
         .query_pairs_mut()
         .append_pair("libraryId", &args.library_id)
         .append_pair("query", &args.query);
 
-    // Fetch text content if requested
-    let content: Option<String> = if matches!(args.r#type, Some(QueryDocsType::Text)) {
-        let mut txt_url = base_url.clone();
-        txt_url.query_pairs_mut().append_pair("type", "txt");
+    // Only an unfiltered, unranked `text` request uses the API's own text.
+    // Everything else, including `both`, fetches the structured response and
+    // renders the text locally, since snippet filters and re-ranking operate on
+    // the structured response
+    let fetch_text = matches!(docs_type, QueryDocsType::Text) && !args.renders_locally();
+    let wants_json = matches!(docs_type, QueryDocsType::Json | QueryDocsType::Both);
 
-        let txt_req = HttpRequest::new(txt_url.as_str())
-            .with_method("GET")
-            .insert_context7_headers(args.context7_api_key.as_deref());
+    // Fetch text content if requested
+    let content: Option<String> = if fetch_text {
+        match fetch_context(&base_url, "txt", args.context7_api_key.as_deref()) {
+            Ok(body) => Some(body),
+            Err(e) => return e.into(),
+        }
+    } else {
+        None
+    };
 
-        let res = match http_request_with_retry(&txt_req) {
-            Ok(res) => res,
-            Err(e) => return CallToolResult::error(format!("Text request failed: {}", e)),
+    // Fetch JSON content otherwise (also the default when type is omitted)
+    let response: Option<QueryDocsResponse> = if !fetch_text {
+        let body = match fetch_context(&base_url, "json", args.context7_api_key.as_deref()) {
+            Ok(body) => body,
+            Err(e) => return e.into(),
         };
 
-        let body = String::from_utf8_lossy(&res.body()).to_string();
-        if res.status_code() < 200 || res.status_code() >= 300 {
-            return CallToolResult::error(format!(
-                "Text API request failed with status {}: {}",
-                res.status_code(),
-                body,
-            ));
+        match serde_json::from_str::<QueryDocsResponse>(&body) {
+            Ok(mut r) => {
+                if html::is_enabled() {
+                    html::normalize_response(&mut r);
+                }
+                snippets::deduplicate(&mut r);
+                if args.rerank == Some(true) {
+                    rank::rerank(&mut r, &args.query);
+                }
+                snippets::filter(&mut r, &args);
+                Some(r)
+            }
+            Err(e) => {
+                return ToolError::deserialization(format!(
+                    "Failed to deserialize JSON response: {e}"
+                ))
+                .into();
+            }
         }
-
-        Some(body)
     } else {
         None
     };
 
-    // Fetch JSON content if requested (also the default when type is omitted)
     let structured_content: Option<Map<String, Value>> =
-        if matches!(args.r#type, Some(QueryDocsType::Json)) {
-            let mut json_url = base_url;
-            json_url.query_pairs_mut().append_pair("type", "json");
-
-            let json_req = HttpRequest::new(json_url.as_str())
-                .with_method("GET")
-                .insert_context7_headers(args.context7_api_key.as_deref());
-
-            let res = match http_request_with_retry(&json_req) {
-                Ok(res) => res,
-                Err(e) => return CallToolResult::error(e.to_string()),
-            };
-
-            let body = String::from_utf8_lossy(&res.body()).to_string();
-            if res.status_code() < 200 || res.status_code() >= 300 {
-                return CallToolResult::error(format!(
-                    "JSON API request failed with status {}: {}",
-                    res.status_code(),
-                    body,
-                ));
-            }
-
-            let response: QueryDocsResponse = match serde_json::from_str(&body) {
-                Ok(r) => r,
-                Err(e) => {
-                    return CallToolResult::error(format!(
-                        "Failed to deserialize JSON response: {}",
-                        e
-                    ));
-                }
-            };
-
-            match serde_json::to_value(response) {
+        match response.as_ref().filter(|_| wants_json) {
+            Some(response) => match serde_json::to_value(response) {
                 Ok(Value::Object(map)) => Some(map),
                 _ => {
-                    return CallToolResult::error(
-                        "Failed to convert QueryDocsResponse to JSON object".to_string(),
-                    );
+                    return ToolError::deserialization(
+                        "Failed to convert QueryDocsResponse to JSON object",
+                    )
+                    .into();
                 }
-            }
-        } else {
-            None
+            },
+            None => None,
         };
 
-    let result = CallToolResult {
+    // Without a text request, render the structured response locally
+    let text = content
+        .or_else(|| {
+            response
+                .as_ref()
+                .map(|r| render::render_docs(r, render::options()))
+        })
+        .unwrap_or_default();
+
+    let mut result = CallToolResult {
         content: vec![ContentBlock::Text(TextContent {
-            text: content.unwrap_or_else(|| {
-                structured_content
-                    .as_ref()
-                    .and_then(|sc| serde_json::to_string(sc).ok())
-                    .unwrap_or_default()
-            }),
+            text,
             ..Default::default()
         })],
         structured_content,
         ..Default::default()
     };
 
+    meta::stamp(&mut result, Some(&args.library_id));
     cache::put("query_docs", &args, &result);
     result
 }
 
-fn resolve_library_id(input: CallToolRequest) -> CallToolResult {
-    let args: ResolveLibraryIdArguments =
+/// Recovers the structured response of a `fetch_docs` result, or its error.
+fn docs_from_result(result: CallToolResult) -> Result<QueryDocsResponse, ToolError> {
+    if result.is_error == Some(true) {
+        return Err(ToolError::from_result(&result).unwrap_or_else(|| {
+            ToolError::internal(
+                result
+                    .content
+                    .iter()
+                    .find_map(|block| match block {
+                        ContentBlock::Text(text) => Some(text.text.clone()),
+                        _ => None,
+                    })
+                    .unwrap_or_else(|| "Unknown error".to_string()),
+            )
+        }));
+    }
+    result
+        .structured_content
+        .ok_or_else(|| ToolError::deserialization("No structured content returned"))
+        .and_then(|sc| {
+            serde_json::from_value::<QueryDocsResponse>(Value::Object(sc)).map_err(|e| {
+                ToolError::deserialization(format!("Failed to deserialize cached response: {e}"))
+            })
+        })
+}
+
+fn query_docs_multi(input: CallToolRequest) -> CallToolResult {
+    let mut args: QueryDocsMultiArguments =
         match serde_json::from_value(Value::Object(input.request.arguments.unwrap_or_default())) {
             Ok(args) => args,
-            Err(e) => return CallToolResult::error(format!("Invalid arguments: {e}")),
+            Err(e) => {
+                return ToolError::invalid_arguments(format!("Invalid arguments: {e}")).into();
+            }
         };
 
-    if let Some(cached) = cache::get("resolve_library_id", &args) {
-        return cached;
+    if let Err(e) = redact::apply("query", &mut args.query) {
+        return ToolError::policy_denied(e).into();
+    }
+
+    let mut library_ids: Vec<String> = Vec::new();
+    for library_id in args.library_ids {
+        // Invalid IDs are kept as given so fetch_docs reports them per library
+        let library_id = library_id::parse(&library_id)
+            .map(|id| id.to_string())
+            .unwrap_or(library_id);
+        if !library_ids.contains(&library_id) {
+            library_ids.push(library_id);
+        }
+    }
+    if library_ids.is_empty() {
+        return ToolError::invalid_arguments(
+            "Invalid arguments: libraryIds must contain at least one library ID",
+        )
+        .into();
+    }
+
+    let mut libraries: Vec<LibraryDocs> = library_ids
+        .into_iter()
+        .map(|library_id| {
+            let result = fetch_docs(QueryDocsArguments {
+                library_id: library_id.clone(),
+                query: args.query.clone(),
+                r#type: Some(QueryDocsType::Json),
+                rerank: args.rerank,
+                context7_api_key: args.context7_api_key.clone(),
+                ..Default::default()
+            });
+            let docs = docs_from_result(result);
+            match docs {
+                Ok(docs) => LibraryDocs {
+                    library_id,
+                    docs: Some(docs),
+                    ..Default::default()
+                },
+                Err(error) => LibraryDocs {
+                    library_id,
+                    error: Some(error),
+                    ..Default::default()
+                },
+            }
+        })
+        .collect();
+
+    if let Some(budget) = args.tokens {
+        snippets::allocate_budget(&mut libraries, budget as f64);
+    }
+
+    let text = libraries
+        .iter()
+        .map(|library| match (&library.docs, &library.error) {
+            (Some(docs), _) => format!(
+                "# {}\n\n{}",
+                library.library_id,
+                render::render_docs(docs, render::options())
+            ),
+            (None, error) => format!(
+                "# {}\n\nError: {}",
+                library.library_id,
+                error
+                    .as_ref()
+                    .map_or("Unknown error", |e| e.message.as_str())
+            ),
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    let all_failed = libraries.iter().all(|library| library.docs.is_none());
+
+    let mut result = CallToolResult {
+        content: vec![ContentBlock::Text(TextContent {
+            text,
+            ..Default::default()
+        })],
+        is_error: all_failed.then_some(true),
+        ..Default::default()
+    };
+    if let Ok(Value::Object(map)) = serde_json::to_value(QueryDocsMultiResponse { libraries }) {
+        result.structured_content = Some(map);
+    }
+    result
+}
+
+/// Answers a question from a library's documentation: fetches re-ranked
+/// snippets like `query_docs`, then asks the client's model to answer from
+/// them via sampling.
+fn answer_from_docs(input: CallToolRequest) -> CallToolResult {
+    let mut args: AnswerFromDocsArguments =
+        match serde_json::from_value(Value::Object(input.request.arguments.unwrap_or_default())) {
+            Ok(args) => args,
+            Err(e) => {
+                return ToolError::invalid_arguments(format!("Invalid arguments: {e}")).into();
+            }
+        };
+
+    if let Err(e) = redact::apply("query", &mut args.query) {
+        return ToolError::policy_denied(e).into();
     }
 
+    // Re-ranking puts the most relevant snippets first, so they survive both
+    // maxSnippets and the prompt size limit
+    let docs = match docs_from_result(fetch_docs(QueryDocsArguments {
+        library_id: args.library_id.clone(),
+        query: args.query.clone(),
+        r#type: Some(QueryDocsType::Json),
+        max_snippets: args.max_snippets,
+        rerank: Some(true),
+        context7_api_key: args.context7_api_key.clone(),
+        ..Default::default()
+    })) {
+        Ok(docs) => docs,
+        Err(e) => return e.into(),
+    };
+    let library_id = library_id::parse(&args.library_id)
+        .map(|id| id.to_string())
+        .unwrap_or(args.library_id);
+
+    let excerpts = answer::excerpts(&docs);
+    if excerpts.is_empty() {
+        return ToolError::new(
+            ErrorKind::NotFound,
+            format!("No documentation found in {library_id} for this query"),
+        )
+        .into();
+    }
+
+    let (text, model, used) =
+        match answer::synthesize(&library_id, &args.query, &excerpts, args.max_tokens) {
+            Ok(answer) => answer,
+            Err(e) => {
+                return ToolError::internal(format!(
+                    "Unable to write an answer with the client's model ({e}). The client may not \
+                     support sampling; use query_docs to get the documentation instead."
+                ))
+                .into();
+            }
+        };
+
+    let citations: Vec<Citation> = answer::citations(&text, &excerpts[..used])
+        .into_iter()
+        .map(|excerpt| Citation {
+            id: excerpt.id.clone(),
+            title: excerpt.title.clone(),
+        })
+        .collect();
+
+    let mut content = text.clone();
+    if !citations.is_empty() {
+        content.push_str("\n\nSources:");
+        for citation in &citations {
+            content.push_str(&format!("\n- [{}] {}", citation.id, citation.title));
+        }
+    }
+
+    let response = AnswerFromDocsResponse {
+        library_id,
+        answer: text,
+        citations,
+        model,
+        snippets_used: used as u32,
+    };
+    let mut result = CallToolResult {
+        content: vec![ContentBlock::Text(TextContent {
+            text: content,
+            ..Default::default()
+        })],
+        ..Default::default()
+    };
+    if let Ok(Value::Object(map)) = serde_json::to_value(response) {
+        result.structured_content = Some(map);
+    }
+    result
+}
+
+/// Reports where the API key comes from and checks it with a single search
+/// request. Unlike the other tools the request is not retried, rotated or
+/// followed by a key prompt, so the status reflects the chosen key alone.
+fn context7_status(input: CallToolRequest) -> CallToolResult {
+    let args: StatusArguments =
+        match serde_json::from_value(Value::Object(input.request.arguments.unwrap_or_default())) {
+            Ok(args) => args,
+            Err(e) => {
+                return ToolError::invalid_arguments(format!("Invalid arguments: {e}")).into();
+            }
+        };
+
+    let key = api_key::select(args.context7_api_key.as_deref());
     let mut url = match Url::parse(&format!("{}/v2/libs/search", CONTEXT7_API_BASE_URL)) {
         Ok(url) => url,
-        Err(e) => {
-            return CallToolResult::error(e.to_string());
+        Err(e) => return ToolError::internal(e.to_string()).into(),
+    };
+    url.query_pairs_mut()
+        .append_pair("libraryName", "react")
+        .append_pair("query", "status check");
+    let mut req = HttpRequest::new(url.as_str())
+        .with_method("GET")
+        .insert_context7_headers();
+    api_key::authorize(&mut req, key.as_ref());
+
+    let mut response = StatusResponse {
+        key_source: key.as_ref().map(|k| k.source),
+        key_position: key.as_ref().and_then(|k| k.pool_position()),
+        configured_keys: api_key::pool_size(),
+        auth_status: AuthStatus::Unreachable,
+        http_status: None,
+        rate_limit: RateLimitHeaders::default(),
+        error: None,
+        plugin_version: env!("CARGO_PKG_VERSION").to_string(),
+        base_url: CONTEXT7_API_BASE_URL.to_string(),
+    };
+    match http::request::<()>(&req, None) {
+        Ok(res) => {
+            ratelimit::record(&api_key::credential(key.as_ref()), &res);
+            let status = res.status_code();
+            let header = |name| ratelimit::header(&res, name).map(str::to_string);
+            response.http_status = Some(status);
+            response.rate_limit = RateLimitHeaders {
+                limit: header("x-ratelimit-limit"),
+                remaining: header("x-ratelimit-remaining"),
+                reset: header("x-ratelimit-reset"),
+                retry_after: header("retry-after"),
+            };
+            response.auth_status = match status {
+                200..=299 if key.is_some() => AuthStatus::Valid,
+                200..=299 => AuthStatus::Anonymous,
+                401 | 403 => AuthStatus::Rejected,
+                429 => AuthStatus::RateLimited,
+                _ => AuthStatus::Error,
+            };
+            if !(200..300).contains(&status) {
+                response.error = response_body(&res, "Search API").err().map(|e| e.message);
+            }
         }
+        Err(e) => response.error = Some(format!("Search API request failed: {e}")),
+    }
+
+    let mut lines = vec![
+        format!(
+            "API key: {}",
+            match (response.key_source, response.key_position) {
+                (None, _) => "none (anonymous access)".to_string(),
+                (Some(source), Some(position)) => format!(
+                    "{} (key {position} of {})",
+                    key_source_name(source),
+                    response.configured_keys
+                ),
+                (Some(source), None) => key_source_name(source).to_string(),
+            }
+        ),
+        format!(
+            "Auth status: {}{}",
+            auth_status_name(response.auth_status),
+            response
+                .http_status
+                .map(|s| format!(" (HTTP {s})"))
+                .unwrap_or_default()
+        ),
+    ];
+    let limits = &response.rate_limit;
+    if limits.limit.is_some() || limits.remaining.is_some() || limits.reset.is_some() {
+        lines.push(format!(
+            "Rate limit: {} remaining of {}, resets {}",
+            limits.remaining.as_deref().unwrap_or("?"),
+            limits.limit.as_deref().unwrap_or("?"),
+            limits.reset.as_deref().unwrap_or("?")
+        ));
+    }
+    if let Some(retry_after) = &limits.retry_after {
+        lines.push(format!("Retry after: {retry_after}"));
+    }
+    if let Some(error) = &response.error {
+        lines.push(format!("Error: {error}"));
+    }
+    lines.push(format!("Plugin version: {}", response.plugin_version));
+    lines.push(format!("Base URL: {}", response.base_url));
+
+    let mut result = CallToolResult {
+        content: vec![ContentBlock::Text(TextContent {
+            text: lines.join("\n"),
+            ..Default::default()
+        })],
+        ..Default::default()
     };
+    if let Ok(Value::Object(map)) = serde_json::to_value(&response) {
+        result.structured_content = Some(map);
+    }
+    result
+}
+
+fn key_source_name(source: KeySource) -> &'static str {
+    match source {
+        KeySource::Argument => "tool argument",
+        KeySource::Session => "entered this session",
+        KeySource::Keyring => "keyring",
+        KeySource::Config => "plain-text config",
+    }
+}
+
+fn auth_status_name(status: AuthStatus) -> &'static str {
+    match status {
+        AuthStatus::Valid => "valid",
+        AuthStatus::Anonymous => "anonymous",
+        AuthStatus::Rejected => "rejected",
+        AuthStatus::RateLimited => "rate limited",
+        AuthStatus::Error => "error",
+        AuthStatus::Unreachable => "unreachable",
+    }
+}
+
+/// Searches `/v2/libs/search`, returning the parsed response.
+fn search_libraries(
+    library_name: &str,
+    query: &str,
+    context7_api_key: Option<&str>,
+) -> Result<ResolveLibraryIdResponse, ToolError> {
+    let mut url = Url::parse(&format!("{}/v2/libs/search", CONTEXT7_API_BASE_URL))
+        .map_err(|e| ToolError::internal(e.to_string()))?;
     url.query_pairs_mut()
-        .append_pair("libraryName", &args.library_name)
-        .append_pair("query", &args.query);
+        .append_pair("libraryName", library_name)
+        .append_pair("query", query);
 
     let req = HttpRequest::new(url.as_str())
         .with_method("GET")
-        .insert_context7_headers(args.context7_api_key.as_deref());
+        .insert_context7_headers();
 
-    match http_request_with_retry(&req) {
-        Ok(res) => {
-            let body_str = String::from_utf8_lossy(&res.body()).to_string();
-            if res.status_code() >= 200 && res.status_code() < 300 {
-                match serde_json::from_str::<ResolveLibraryIdResponse>(&body_str) {
-                    Ok(context7_response) => {
-                        let mut call_tool_result = CallToolResult {
-                            content: vec![ContentBlock::Text(TextContent {
-                                text: body_str,
-
-                                ..Default::default()
-                            })],
-
-                            ..Default::default()
-                        };
-                        if let Ok(Value::Object(map)) = serde_json::to_value(context7_response) {
-                            call_tool_result.structured_content = Some(map);
-                        }
-
-                        cache::put("resolve_library_id", &args, &call_tool_result);
-                        call_tool_result
-                    }
-                    Err(e) => CallToolResult::error(e.to_string()),
-                }
-            } else {
-                CallToolResult::error(format!(
-                    "API request failed with status {}: {}",
-                    res.status_code(),
-                    body_str,
-                ))
+    let res = http_request_with_retry(&req, context7_api_key, "Search API")?;
+    let body = response_body(&res, "Search API")?;
+
+    serde_json::from_str::<ResolveLibraryIdResponse>(&body).map_err(|e| {
+        ToolError::deserialization(format!("Failed to deserialize search response: {e}"))
+    })
+}
+
+/// Search term for an exact library ID: its org and project, since generic
+/// project names such as `docs` alone rarely bring back the right library.
+fn library_search_term(library_id: &str) -> String {
+    policy::base_id(library_id)
+        .trim_start_matches('/')
+        .replace('/', " ")
+}
+
+/// Looks up the record for a library ID through the `get_library_info` cache,
+/// searching for the library and keeping the result whose ID matches exactly
+/// (ignoring case and any version suffix). Returns the `get_library_info`
+/// result, or `None` when no library has that ID.
+fn library_info(
+    library_id: &str,
+    context7_api_key: Option<&str>,
+) -> Result<Option<CallToolResult>, ToolError> {
+    let base_id = policy::base_id(library_id);
+    let args = GetLibraryInfoArguments {
+        library_id: base_id.clone(),
+        context7_api_key: None,
+    };
+    if let Some(mut cached) = cache::get(cache::LIBRARY_INFO_TOOL, &args) {
+        let id = cached
+            .structured_content
+            .as_ref()
+            .and_then(|library| library.get("id"))
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        meta::mark_cached(&mut cached, id.as_deref());
+        return Ok(Some(cached));
+    }
+
+    let term = library_search_term(library_id);
+    let response = search_libraries(&term, &term, context7_api_key)?;
+    let Some(library) = response
+        .results
+        .into_iter()
+        .find(|library| policy::base_id(&library.id) == base_id)
+    else {
+        return Ok(None);
+    };
+
+    let mut result = CallToolResult {
+        content: vec![ContentBlock::Text(TextContent {
+            text: serde_json::to_string_pretty(&library).unwrap_or_default(),
+            ..Default::default()
+        })],
+        ..Default::default()
+    };
+    if let Ok(Value::Object(map)) = serde_json::to_value(&library) {
+        result.structured_content = Some(map);
+    }
+    meta::stamp(&mut result, Some(&library.id));
+    cache::put(cache::LIBRARY_INFO_TOOL, &args, &result);
+    Ok(Some(result))
+}
+
+/// The `Library` record for a library ID, as returned by [`library_info`].
+fn lookup_library(
+    library_id: &str,
+    context7_api_key: Option<&str>,
+) -> Result<Option<Library>, ToolError> {
+    Ok(library_info(library_id, context7_api_key)?
+        .and_then(|result| result.structured_content)
+        .and_then(|library| serde_json::from_value(Value::Object(library)).ok()))
+}
+
+/// Checks a library ID against the access policy. The trust score floor needs
+/// the library's metadata, so it costs a search when `MIN_TRUST_SCORE` is set.
+/// Blocked requests are logged.
+fn check_library_policy(library_id: &str, context7_api_key: Option<&str>) -> Result<(), ToolError> {
+    let policy = policy::policy();
+    let mut result = policy.check_id(library_id);
+    if result.is_ok() && policy.min_trust_score.is_some() {
+        result = match lookup_library(library_id, context7_api_key)? {
+            Some(library) => policy.check_trust(library_id, library.trust_score),
+            None => policy.check_trust(library_id, None),
+        };
+    }
+    result.map_err(|e| {
+        policy::notify_blocked(&e);
+        ToolError::policy_denied(e)
+    })
+}
+
+fn resolve_library_id(input: CallToolRequest) -> CallToolResult {
+    let mut args: ResolveLibraryIdArguments =
+        match serde_json::from_value(Value::Object(input.request.arguments.unwrap_or_default())) {
+            Ok(args) => args,
+            Err(e) => {
+                return ToolError::invalid_arguments(format!("Invalid arguments: {e}")).into();
+            }
+        };
+
+    if let Err(e) = redact::apply("libraryName", &mut args.library_name)
+        .and_then(|_| redact::apply("query", &mut args.query))
+    {
+        return ToolError::policy_denied(e).into();
+    }
+
+    meta::reset();
+    let alias = aliases::lookup(&args.library_name);
+    if alias.is_none()
+        && let Some(mut cached) = cache::get("resolve_library_id", &args)
+    {
+        let mut response: Option<ResolveLibraryIdResponse> = cached
+            .structured_content
+            .clone()
+            .and_then(|sc| serde_json::from_value(Value::Object(sc)).ok());
+        // The entry may predate a policy change, so blocked libraries are
+        // dropped again
+        if let Some(response) = &mut response {
+            let blocked = policy::policy().refilter(response);
+            if blocked > 0 {
+                policy::notify_blocked(&format!(
+                    "Blocked {blocked} cached library result(s) for '{}' by policy",
+                    args.library_name
+                ));
+                // Keep the near-match notice ahead of the re-rendered results
+                let notices = cached.content.len().saturating_sub(1);
+                let mut filtered = resolve_result(response, &args);
+                filtered
+                    .content
+                    .splice(0..0, cached.content.drain(..notices));
+                filtered.meta = cached.meta.take();
+                cached = filtered;
             }
         }
-        Err(e) => CallToolResult::error(e.to_string()),
+        let mut result = match response.and_then(|response| disambiguate(&response, &args)) {
+            Some(mut selected) => {
+                selected.meta = cached.meta;
+                selected
+            }
+            None => cached,
+        };
+        let library_id = best_match(&result);
+        meta::mark_cached(&mut result, library_id.as_deref());
+        return result;
     }
+
+    let policy = policy::policy();
+    let context7_response = match alias {
+        Some(library_id) => {
+            if let Err(e) = check_library_policy(library_id, args.context7_api_key.as_deref()) {
+                return e.into();
+            }
+            aliases::resolve(&args.library_name, library_id)
+        }
+        None => {
+            let mut response = match search_libraries(
+                &args.library_name,
+                &args.query,
+                args.context7_api_key.as_deref(),
+            ) {
+                Ok(response) => response,
+                Err(e) => return e.into(),
+            };
+
+            let blocked = policy.filter(&mut response);
+            if blocked > 0 {
+                policy::notify_blocked(&format!(
+                    "Blocked {blocked} library result(s) for '{}' by policy",
+                    args.library_name
+                ));
+                response.blocked_by_policy = Some(blocked);
+            }
+            resolve::filter_libraries(&mut response, &args);
+            resolve::select_best_match(&mut response, &args.library_name);
+            if let Some(limit) = args.limit {
+                response.results.truncate(limit as usize);
+            }
+            response
+        }
+    };
+
+    let mut call_tool_result = resolve_result(&context7_response, &args);
+    meta::stamp(
+        &mut call_tool_result,
+        context7_response.best_match.as_deref(),
+    );
+    if alias.is_some() {
+        return call_tool_result;
+    }
+
+    cache::put("resolve_library_id", &args, &call_tool_result);
+    match disambiguate(&context7_response, &args) {
+        Some(mut selected) => {
+            let library_id = best_match(&selected);
+            meta::stamp(&mut selected, library_id.as_deref());
+            selected
+        }
+        None => call_tool_result,
+    }
+}
+
+/// The `bestMatch` library ID of a `resolve_library_id` result.
+fn best_match(result: &CallToolResult) -> Option<String> {
+    result
+        .structured_content
+        .as_ref()?
+        .get("bestMatch")?
+        .as_str()
+        .map(str::to_string)
+}
+
+fn resolve_result(
+    response: &ResolveLibraryIdResponse,
+    args: &ResolveLibraryIdArguments,
+) -> CallToolResult {
+    let mut call_tool_result = CallToolResult {
+        content: vec![ContentBlock::Text(TextContent {
+            text: resolve::format_libraries(response, args.format.unwrap_or(ResolveFormat::Json)),
+
+            ..Default::default()
+        })],
+
+        ..Default::default()
+    };
+    if let Ok(Value::Object(map)) = serde_json::to_value(response) {
+        call_tool_result.structured_content = Some(map);
+    }
+    call_tool_result
+}
+
+/// When `DISAMBIGUATE_LIBRARIES` is on and several libraries score close to
+/// the best match, asks the user to pick one and returns a result containing
+/// only that library. The cached result keeps every candidate.
+fn disambiguate(
+    response: &ResolveLibraryIdResponse,
+    args: &ResolveLibraryIdArguments,
+) -> Option<CallToolResult> {
+    if !elicitation::disambiguation_enabled() {
+        return None;
+    }
+    let candidates = resolve::close_matches(response);
+    if candidates.len() < 2 {
+        return None;
+    }
+
+    let library_id = elicitation::choose_library(&args.library_name, &candidates)?;
+    let library = candidates.into_iter().find(|l| l.id == library_id)?.clone();
+    let selected = ResolveLibraryIdResponse {
+        results: vec![library],
+        best_match: Some(library_id),
+        selected_by_user: Some(true),
+        ..Default::default()
+    };
+    Some(resolve_result(&selected, args))
+}
+
+/// Returns the `Library` record for an exact library ID. The API has no
+/// details endpoint, so the record comes from a search (see [`library_info`]).
+fn get_library_info(input: CallToolRequest) -> CallToolResult {
+    let mut args: GetLibraryInfoArguments =
+        match serde_json::from_value(Value::Object(input.request.arguments.unwrap_or_default())) {
+            Ok(args) => args,
+            Err(e) => {
+                return ToolError::invalid_arguments(format!("Invalid arguments: {e}")).into();
+            }
+        };
+
+    match library_id::parse(&args.library_id) {
+        Ok(library_id) => args.library_id = library_id.to_string(),
+        Err(e) => return ToolError::invalid_arguments(e).into(),
+    }
+
+    let policy = policy::policy();
+    if let Err(e) = policy.check_id(&args.library_id) {
+        policy::notify_blocked(&e);
+        return ToolError::policy_denied(e).into();
+    }
+
+    meta::reset();
+    let result = match library_info(&args.library_id, args.context7_api_key.as_deref()) {
+        Ok(Some(result)) => result,
+        Ok(None) => {
+            return ToolError::new(
+                ErrorKind::NotFound,
+                format!("No library found with ID {}", args.library_id),
+            )
+            .into();
+        }
+        Err(e) => return e.into(),
+    };
+    let trust_score = result
+        .structured_content
+        .as_ref()
+        .and_then(|library| library.get("trustScore"))
+        .and_then(Value::as_f64);
+    if let Err(e) = policy.check_trust(&args.library_id, trust_score) {
+        policy::notify_blocked(&e);
+        return ToolError::policy_denied(e).into();
+    }
+    result
 }
 
 // Stub functions for MCP handlers not implemented in this tools-only plugin
@@ -0,0 +1,161 @@
+use crate::{
+    pdk::{imports::notify_logging_message, types::*},
+    settings,
+};
+use serde_json::{Map, Value};
+use std::{collections::BTreeMap, sync::OnceLock};
+
+static LOG_LEVEL: OnceLock<LoggingLevel> = OnceLock::new();
+
+/// Headers whose values are never logged.
+const SECRET_HEADERS: &[&str] = &["authorization", "cookie", "x-api-key"];
+/// Prefix of Context7 API keys.
+const CONTEXT7_KEY_PREFIX: &str = "ctx7sk";
+const REDACTED: &str = "[REDACTED]";
+
+fn severity(level: &LoggingLevel) -> u8 {
+    match level {
+        LoggingLevel::Debug => 0,
+        LoggingLevel::Info => 1,
+        LoggingLevel::Notice => 2,
+        LoggingLevel::Warning => 3,
+        LoggingLevel::Error => 4,
+        LoggingLevel::Critical => 5,
+        LoggingLevel::Alert => 6,
+        LoggingLevel::Emergency => 7,
+    }
+}
+
+/// Minimum level sent to the client, read from `LOG_LEVEL` (default `info`).
+fn min_level() -> &'static LoggingLevel {
+    LOG_LEVEL.get_or_init(|| {
+        match settings::get("LOG_LEVEL")
+            .map(|v| v.to_lowercase())
+            .as_deref()
+        {
+            Some("debug" | "trace") => LoggingLevel::Debug,
+            Some("notice") => LoggingLevel::Notice,
+            Some("warning" | "warn") => LoggingLevel::Warning,
+            Some("error") => LoggingLevel::Error,
+            Some("critical") => LoggingLevel::Critical,
+            Some("alert") => LoggingLevel::Alert,
+            Some("emergency") => LoggingLevel::Emergency,
+            _ => LoggingLevel::Info,
+        }
+    })
+}
+
+/// Whether events at `level` are sent. Lets callers skip building expensive
+/// debug fields.
+pub(crate) fn enabled(level: LoggingLevel) -> bool {
+    severity(&level) >= severity(min_level())
+}
+
+/// Masks bearer tokens and Context7 API keys in `text`.
+pub(crate) fn scrub(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut after_bearer = false;
+    for (i, word) in text.split(' ').enumerate() {
+        if i > 0 {
+            out.push(' ');
+        }
+        let token = word.trim_matches(|c: char| !c.is_ascii_alphanumeric() && c != '_');
+        if (after_bearer && !token.is_empty()) || token.starts_with(CONTEXT7_KEY_PREFIX) {
+            out.push_str(&word.replacen(token, REDACTED, 1));
+        } else {
+            out.push_str(word);
+        }
+        after_bearer = word.eq_ignore_ascii_case("bearer");
+    }
+    out
+}
+
+fn scrub_value(value: Value) -> Value {
+    match value {
+        Value::String(text) => Value::String(scrub(&text)),
+        Value::Array(items) => Value::Array(items.into_iter().map(scrub_value).collect()),
+        Value::Object(map) => {
+            Value::Object(map.into_iter().map(|(k, v)| (k, scrub_value(v))).collect())
+        }
+        other => other,
+    }
+}
+
+/// Request headers for a trace, with credentials masked.
+pub(crate) fn headers(headers: &BTreeMap<String, String>) -> Value {
+    Value::Object(
+        headers
+            .iter()
+            .map(|(name, value)| {
+                let value = if SECRET_HEADERS.contains(&name.to_lowercase().as_str()) {
+                    REDACTED.to_string()
+                } else {
+                    value.clone()
+                };
+                (name.clone(), Value::String(value))
+            })
+            .collect(),
+    )
+}
+
+/// A log event: a message plus structured fields, sent as the `data` object
+/// of a logging notification from the named logger.
+#[must_use = "call `send` to emit the event"]
+pub(crate) struct Event {
+    level: LoggingLevel,
+    logger: &'static str,
+    data: Map<String, Value>,
+}
+
+impl Event {
+    /// Adds a field, e.g. `tool`, `libraryId`, `cache`, `status`, `attempt`
+    /// or `latencyMs`.
+    pub(crate) fn field(mut self, name: &str, value: impl Into<Value>) -> Self {
+        self.data.insert(name.to_string(), value.into());
+        self
+    }
+
+    /// Sends the event if its level is enabled. String values are scrubbed of
+    /// API keys before sending.
+    pub(crate) fn send(self) {
+        if !enabled(self.level.clone()) {
+            return;
+        }
+        notify_logging_message(LoggingMessageNotificationParam {
+            data: scrub_value(Value::Object(self.data)),
+            level: self.level,
+            logger: Some(self.logger.to_string()),
+        })
+        .ok();
+    }
+}
+
+pub(crate) fn event(
+    level: LoggingLevel,
+    logger: &'static str,
+    message: impl Into<String>,
+) -> Event {
+    let mut data = Map::new();
+    data.insert("message".to_string(), Value::String(message.into()));
+    Event {
+        level,
+        logger,
+        data,
+    }
+}
+
+pub(crate) fn debug(logger: &'static str, message: impl Into<String>) -> Event {
+    event(LoggingLevel::Debug, logger, message)
+}
+
+pub(crate) fn info(logger: &'static str, message: impl Into<String>) -> Event {
+    event(LoggingLevel::Info, logger, message)
+}
+
+pub(crate) fn warning(logger: &'static str, message: impl Into<String>) -> Event {
+    event(LoggingLevel::Warning, logger, message)
+}
+
+pub(crate) fn error(logger: &'static str, message: impl Into<String>) -> Event {
+    event(LoggingLevel::Error, logger, message)
+}
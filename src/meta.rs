@@ -0,0 +1,126 @@
+use crate::pdk::types::*;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::sync::Mutex;
+
+static STATS: Mutex<UpstreamStats> = Mutex::new(UpstreamStats::new());
+
+/// API requests made while producing the current result.
+#[derive(Debug, Clone, Copy)]
+struct UpstreamStats {
+    attempts: u32,
+    status: Option<u16>,
+    latency_ms: i64,
+}
+
+impl UpstreamStats {
+    const fn new() -> Self {
+        Self {
+            attempts: 0,
+            status: None,
+            latency_ms: 0,
+        }
+    }
+}
+
+/// Provenance added to a result's `_meta`. Upstream fields describe the fetch
+/// that produced the result, so a cached result keeps those of the original
+/// fetch.
+#[derive(Debug, Clone, Serialize)]
+struct ResultMeta {
+    #[serde(rename = "cacheHit")]
+    cache_hit: bool,
+    #[serde(rename = "fetchedAt")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fetched_at: Option<String>,
+    #[serde(rename = "ageSecs")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    age_secs: Option<i64>,
+    #[serde(rename = "upstreamStatus")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    upstream_status: Option<u16>,
+    attempts: u32,
+    #[serde(rename = "latencyMs")]
+    latency_ms: i64,
+    #[serde(rename = "libraryId")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    library_id: Option<String>,
+    #[serde(rename = "pluginVersion")]
+    plugin_version: String,
+}
+
+fn with_stats<T>(f: impl FnOnce(&mut UpstreamStats) -> T) -> T {
+    f(&mut STATS.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+/// Starts counting API requests for a new result.
+pub(crate) fn reset() {
+    with_stats(|stats| *stats = UpstreamStats::new());
+}
+
+/// Records one API request attempt; `status` is `None` for network errors.
+pub(crate) fn record_attempt(status: Option<u16>, latency_ms: i64) {
+    with_stats(|stats| {
+        stats.attempts += 1;
+        stats.status = status.or(stats.status);
+        stats.latency_ms += latency_ms;
+    });
+}
+
+fn insert(result: &mut CallToolResult, meta: ResultMeta) {
+    if let Ok(Value::Object(fields)) = serde_json::to_value(meta) {
+        result.meta.get_or_insert_with(Map::new).extend(fields);
+    }
+}
+
+/// Adds `_meta` to a freshly fetched result, from the requests counted since
+/// the last [`reset`].
+pub(crate) fn stamp(result: &mut CallToolResult, library_id: Option<&str>) {
+    let stats = with_stats(|stats| *stats);
+    insert(
+        result,
+        ResultMeta {
+            cache_hit: false,
+            fetched_at: Some(Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)),
+            age_secs: Some(0),
+            upstream_status: stats.status,
+            attempts: stats.attempts,
+            latency_ms: stats.latency_ms,
+            library_id: library_id.map(str::to_string),
+            plugin_version: env!("CARGO_PKG_VERSION").to_string(),
+        },
+    );
+}
+
+/// Marks a result served from the cache, computing its age from the stored
+/// `fetchedAt`. Entries cached before `_meta` was added have no fetch time.
+pub(crate) fn mark_cached(result: &mut CallToolResult, library_id: Option<&str>) {
+    let stored = result.meta.clone().unwrap_or_default();
+    let fetched_at = stored
+        .get("fetchedAt")
+        .and_then(Value::as_str)
+        .and_then(|t| DateTime::parse_from_rfc3339(t).ok());
+    insert(
+        result,
+        ResultMeta {
+            cache_hit: true,
+            fetched_at: fetched_at.map(|t| t.to_rfc3339_opts(SecondsFormat::Secs, true)),
+            age_secs: fetched_at.map(|t| (Utc::now() - t.with_timezone(&Utc)).num_seconds()),
+            upstream_status: stored
+                .get("upstreamStatus")
+                .and_then(Value::as_u64)
+                .map(|s| s as u16),
+            attempts: stored
+                .get("attempts")
+                .and_then(Value::as_u64)
+                .unwrap_or_default() as u32,
+            latency_ms: stored
+                .get("latencyMs")
+                .and_then(Value::as_i64)
+                .unwrap_or_default(),
+            library_id: library_id.map(str::to_string),
+            plugin_version: env!("CARGO_PKG_VERSION").to_string(),
+        },
+    );
+}
@@ -0,0 +1,140 @@
+use crate::{log, settings, types::*};
+use std::sync::OnceLock;
+
+static POLICY: OnceLock<Policy> = OnceLock::new();
+
+/// Library access policy read from `LIBRARY_ALLOW`, `LIBRARY_DENY` and
+/// `MIN_TRUST_SCORE`.
+#[derive(Debug, Default)]
+pub(crate) struct Policy {
+    pub allow: Vec<String>,
+    pub deny: Vec<String>,
+    pub min_trust_score: Option<f64>,
+}
+
+fn patterns(key: &str) -> Vec<String> {
+    settings::get(key)
+        .map(|v| {
+            v.split(',')
+                .map(|p| p.trim().to_lowercase())
+                .filter(|p| !p.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub(crate) fn policy() -> &'static Policy {
+    POLICY.get_or_init(|| Policy {
+        allow: patterns("LIBRARY_ALLOW"),
+        deny: patterns("LIBRARY_DENY"),
+        min_trust_score: settings::get("MIN_TRUST_SCORE").and_then(|v| v.parse().ok()),
+    })
+}
+
+/// Matches `text` against a glob where `*` matches any run of characters and
+/// `?` matches exactly one.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Returns the `/org/project` part of a library ID, dropping any version.
+pub(crate) fn base_id(library_id: &str) -> String {
+    let trimmed = library_id.trim().trim_matches('/').to_lowercase();
+    let base: Vec<&str> = trimmed.splitn(3, '/').take(2).collect();
+    format!("/{}", base.join("/"))
+}
+
+impl Policy {
+    /// Checks a library ID against the deny and allow patterns, returning the
+    /// reason it is blocked. Deny patterns take precedence.
+    pub(crate) fn check_id(&self, library_id: &str) -> Result<(), String> {
+        let id = base_id(library_id);
+        if let Some(pattern) = self.deny.iter().find(|p| glob_match(p, &id)) {
+            return Err(format!(
+                "Library '{library_id}' is blocked by policy: it matches the deny pattern '{pattern}'"
+            ));
+        }
+        if !self.allow.is_empty() && !self.allow.iter().any(|p| glob_match(p, &id)) {
+            return Err(format!(
+                "Library '{library_id}' is blocked by policy: it does not match any allowed pattern"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks a library's trust score against `MIN_TRUST_SCORE`. Libraries
+    /// without a trust score are blocked when a floor is set.
+    pub(crate) fn check_trust(
+        &self,
+        library_id: &str,
+        trust_score: Option<f64>,
+    ) -> Result<(), String> {
+        let Some(min) = self.min_trust_score else {
+            return Ok(());
+        };
+        match trust_score {
+            Some(score) if score >= min => Ok(()),
+            Some(score) => Err(format!(
+                "Library '{library_id}' is blocked by policy: its trust score {score} is below the minimum {min}"
+            )),
+            None => Err(format!(
+                "Library '{library_id}' is blocked by policy: its trust score is unknown and the minimum is {min}"
+            )),
+        }
+    }
+
+    /// Removes libraries that fail the policy, returning how many were removed.
+    pub(crate) fn filter(&self, response: &mut ResolveLibraryIdResponse) -> u32 {
+        let before = response.results.len();
+        response.results.retain(|library| {
+            self.check_id(&library.id)
+                .and_then(|_| self.check_trust(&library.id, library.trust_score))
+                .is_ok()
+        });
+        (before - response.results.len()) as u32
+    }
+
+    /// Re-applies the policy to a cached response, which may predate a policy
+    /// change. Blocked libraries are added to `blockedByPolicy` and the best
+    /// match moves to the first remaining result. Returns how many were
+    /// removed.
+    pub(crate) fn refilter(&self, response: &mut ResolveLibraryIdResponse) -> u32 {
+        let blocked = self.filter(response);
+        if blocked > 0 {
+            response.blocked_by_policy =
+                Some(response.blocked_by_policy.unwrap_or_default() + blocked);
+            response.best_match = response.results.first().map(|l| l.id.clone());
+        }
+        blocked
+    }
+}
+
+/// Logs a blocked request as a warning.
+pub(crate) fn notify_blocked(message: &str) {
+    log::warning("policy", message).send();
+}
@@ -0,0 +1,105 @@
+use crate::{cache::normalize_query, types::*};
+use std::collections::HashMap;
+
+/// BM25 term frequency saturation.
+const K1: f64 = 1.2;
+/// BM25 document length normalization.
+const B: f64 = 0.75;
+
+fn tokenize(text: &str) -> Vec<String> {
+    normalize_query(text)
+        .split_whitespace()
+        .map(str::to_string)
+        .collect()
+}
+
+fn code_document(snippet: &CodeSnippet) -> Vec<String> {
+    tokenize(&format!(
+        "{} {} {}",
+        snippet.code_title, snippet.code_description, snippet.page_title
+    ))
+}
+
+fn info_document(snippet: &InfoSnippet) -> Vec<String> {
+    tokenize(&format!(
+        "{} {}",
+        snippet.breadcrumb.as_deref().unwrap_or_default(),
+        snippet.content
+    ))
+}
+
+/// Scores each document against the query with Okapi BM25, using the given
+/// documents as the corpus.
+fn bm25(query: &[String], documents: &[Vec<String>]) -> Vec<f64> {
+    if documents.is_empty() {
+        return Vec::new();
+    }
+
+    let count = documents.len() as f64;
+    let average_length = documents.iter().map(Vec::len).sum::<usize>() as f64 / count;
+
+    let mut document_frequency: HashMap<&str, f64> = HashMap::new();
+    for term in query {
+        if document_frequency.contains_key(term.as_str()) {
+            continue;
+        }
+        let frequency = documents.iter().filter(|d| d.contains(term)).count() as f64;
+        document_frequency.insert(term, frequency);
+    }
+
+    documents
+        .iter()
+        .map(|document| {
+            let length_norm = if average_length > 0.0 {
+                1.0 - B + B * document.len() as f64 / average_length
+            } else {
+                1.0
+            };
+            document_frequency
+                .iter()
+                .map(|(term, frequency)| {
+                    let tf = document.iter().filter(|t| t == term).count() as f64;
+                    let idf = (1.0 + (count - frequency + 0.5) / (frequency + 0.5)).ln();
+                    idf * tf * (K1 + 1.0) / (tf + K1 * length_norm)
+                })
+                .sum()
+        })
+        .collect()
+}
+
+/// Scores every snippet against the query, stores the score (scaled to 0-1)
+/// in its `relevance` field, and sorts both lists by relevance. Code and info
+/// snippets share one corpus so their scores are comparable.
+pub(crate) fn rerank(response: &mut QueryDocsResponse, query: &str) {
+    let query = tokenize(query);
+    let documents: Vec<Vec<String>> = response
+        .code_snippets
+        .iter()
+        .map(code_document)
+        .chain(response.info_snippets.iter().map(info_document))
+        .collect();
+
+    let scores = bm25(&query, &documents);
+    let max = scores.iter().copied().fold(0.0, f64::max);
+    let scale = |score: f64| {
+        let relevance = if max > 0.0 { score / max } else { 0.0 };
+        (relevance * 1000.0).round() / 1000.0
+    };
+
+    let (code_scores, info_scores) = scores.split_at(response.code_snippets.len());
+    for (snippet, score) in response.code_snippets.iter_mut().zip(code_scores) {
+        snippet.relevance = Some(scale(*score));
+    }
+    for (snippet, score) in response.info_snippets.iter_mut().zip(info_scores) {
+        snippet.relevance = Some(scale(*score));
+    }
+
+    let by_relevance =
+        |a: Option<f64>, b: Option<f64>| b.unwrap_or_default().total_cmp(&a.unwrap_or_default());
+    response
+        .code_snippets
+        .sort_by(|a, b| by_relevance(a.relevance, b.relevance));
+    response
+        .info_snippets
+        .sort_by(|a, b| by_relevance(a.relevance, b.relevance));
+}
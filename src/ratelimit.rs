@@ -0,0 +1,245 @@
+use crate::{
+    cache,
+    errors::{ErrorKind, ToolError},
+    log, settings,
+};
+use chrono::{DateTime, TimeZone, Utc};
+use extism_pdk::HttpResponse;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    sync::{Mutex, OnceLock},
+    thread,
+    time::Duration,
+};
+
+/// Rate-limit state file. The extension is not `.json` so `clear_cache` keeps
+/// it.
+const STATE_PATH: &str = "/cache/ratelimit.state";
+const DEFAULT_MAX_WAIT_SECS: u64 = 10;
+/// Block applied after a 429 without a `Retry-After` header.
+const DEFAULT_BLOCK_SECS: i64 = 15;
+/// Window for the anonymous requests-per-minute cap.
+const WINDOW_SECS: i64 = 60;
+/// `X-RateLimit-Reset` values above this are Unix timestamps rather than
+/// seconds from now.
+const EPOCH_THRESHOLD: i64 = 1_000_000_000;
+
+/// Credential name for requests sent without an API key.
+pub(crate) const ANONYMOUS: &str = "anonymous";
+
+/// State per credential, so one exhausted key does not hold requests made
+/// with another.
+static STATE: Mutex<Option<BTreeMap<String, RateLimitState>>> = Mutex::new(None);
+static SETTINGS: OnceLock<RateLimitSettings> = OnceLock::new();
+
+/// Budget reported by the API and local request history, as Unix timestamps.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+struct RateLimitState {
+    #[serde(default)]
+    remaining: Option<u64>,
+    #[serde(rename = "resetAt")]
+    #[serde(default)]
+    reset_at: Option<i64>,
+    #[serde(rename = "blockedUntil")]
+    #[serde(default)]
+    blocked_until: Option<i64>,
+    /// Start times of recent anonymous requests.
+    #[serde(default)]
+    anonymous: Vec<i64>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct RateLimitSettings {
+    /// Longest delay taken before a request instead of failing fast.
+    max_wait: u64,
+    /// Local cap on anonymous requests per minute.
+    anonymous_per_minute: Option<u32>,
+}
+
+fn rate_limit_settings() -> RateLimitSettings {
+    *SETTINGS.get_or_init(|| RateLimitSettings {
+        max_wait: settings::get("RATE_LIMIT_MAX_WAIT")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MAX_WAIT_SECS),
+        anonymous_per_minute: settings::get("ANONYMOUS_REQUESTS_PER_MINUTE")
+            .and_then(|v| v.parse().ok())
+            .filter(|cap| *cap > 0),
+    })
+}
+
+/// Parses a `Retry-After` header given in seconds or as an HTTP date.
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let target = if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    } else if let Ok(date) = DateTime::parse_from_rfc2822(value) {
+        date.with_timezone(&Utc)
+    } else if let Ok(date) = chrono::NaiveDateTime::parse_from_str(value, "%a %b %e %H:%M:%S %Y") {
+        date.and_utc()
+    } else {
+        return None;
+    };
+    (target - Utc::now()).to_std().ok()
+}
+
+/// Looks up a response header, ignoring case.
+pub(crate) fn header<'a>(res: &'a HttpResponse, name: &str) -> Option<&'a str> {
+    res.headers()
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// The `Retry-After` delay of a response, if it has one.
+pub(crate) fn retry_after(res: &HttpResponse) -> Option<Duration> {
+    header(res, "retry-after").and_then(parse_retry_after)
+}
+
+/// Parses `X-RateLimit-Reset`, which may be a Unix timestamp or a number of
+/// seconds from now.
+fn parse_reset(value: &str, now: i64) -> Option<i64> {
+    let value = value.trim().parse::<f64>().ok()? as i64;
+    Some(if value > EPOCH_THRESHOLD {
+        value
+    } else {
+        now + value
+    })
+}
+
+/// Updates the state from a response's rate-limit headers.
+fn update(
+    state: &mut RateLimitState,
+    now: i64,
+    status: u16,
+    remaining: Option<&str>,
+    reset: Option<&str>,
+    retry_after: Option<Duration>,
+) {
+    if let Some(remaining) = remaining.and_then(|v| v.trim().parse().ok()) {
+        state.remaining = Some(remaining);
+    }
+    if let Some(reset) = reset.and_then(|v| parse_reset(v, now)) {
+        state.reset_at = Some(reset);
+    }
+    if status == 429 {
+        let wait = retry_after.map_or(DEFAULT_BLOCK_SECS, |d| d.as_secs() as i64);
+        state.blocked_until = Some(now + wait.max(1));
+    } else if (200..300).contains(&status) {
+        state.blocked_until = None;
+    }
+}
+
+/// Returns the time the next request must wait until, if any, and records an
+/// anonymous request when it may proceed.
+fn next_allowed(
+    state: &mut RateLimitState,
+    now: i64,
+    anonymous: bool,
+    anonymous_per_minute: Option<u32>,
+) -> Option<i64> {
+    state.blocked_until = state.blocked_until.filter(|until| *until > now);
+    if state.reset_at.is_some_and(|reset| reset <= now) {
+        state.remaining = None;
+        state.reset_at = None;
+    }
+    state.anonymous.retain(|at| *at > now - WINDOW_SECS);
+
+    let mut until = state.blocked_until;
+    if state.remaining == Some(0) {
+        until = until.max(state.reset_at);
+    }
+    if anonymous
+        && let Some(cap) = anonymous_per_minute
+        && state.anonymous.len() >= cap as usize
+    {
+        let oldest = state.anonymous.iter().min().copied().unwrap_or(now);
+        until = until.max(Some(oldest + WINDOW_SECS));
+    }
+
+    if until.is_none() && anonymous && anonymous_per_minute.is_some() {
+        state.anonymous.push(now);
+    }
+    until
+}
+
+fn load() -> BTreeMap<String, RateLimitState> {
+    if !cache::is_enabled() {
+        return BTreeMap::new();
+    }
+    fs::read_to_string(STATE_PATH)
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+fn save(state: &BTreeMap<String, RateLimitState>) {
+    if cache::is_enabled()
+        && let Ok(data) = serde_json::to_string(state)
+    {
+        let _ = fs::write(STATE_PATH, data);
+    }
+}
+
+/// Runs `f` with the state of `credential`, loading all state from `/cache`
+/// on first use and persisting it afterwards.
+fn with_state<T>(credential: &str, f: impl FnOnce(&mut RateLimitState) -> T) -> T {
+    let mut guard = STATE.lock().unwrap_or_else(|e| e.into_inner());
+    let states = guard.get_or_insert_with(load);
+    let result = f(states.entry(credential.to_string()).or_default());
+    save(states);
+    result
+}
+
+/// Waits until a request with `credential` may be sent, or fails fast when the
+/// wait would exceed `RATE_LIMIT_MAX_WAIT`. Without `may_wait`, any hold fails
+/// fast so the caller can switch credentials instead.
+pub(crate) fn before_request(credential: &str, may_wait: bool) -> Result<(), ToolError> {
+    let settings = rate_limit_settings();
+    let anonymous = credential == ANONYMOUS;
+    loop {
+        let now = Utc::now().timestamp();
+        let Some(until) = with_state(credential, |state| {
+            next_allowed(state, now, anonymous, settings.anonymous_per_minute)
+        }) else {
+            return Ok(());
+        };
+
+        let wait = (until - now).max(1) as u64;
+        log::debug("ratelimit", "Request held by rate limit")
+            .field("waitSecs", wait)
+            .field("credential", credential)
+            .send();
+        if !may_wait || wait > settings.max_wait {
+            let until = Utc
+                .timestamp_opt(until, 0)
+                .single()
+                .map(|t| t.to_rfc3339())
+                .unwrap_or_default();
+            return Err(ToolError::new(
+                ErrorKind::RateLimited {
+                    retry_after: Some(wait),
+                },
+                format!("Rate limited until {until} (retry in {wait}s)"),
+            ));
+        }
+        thread::sleep(Duration::from_secs(wait));
+    }
+}
+
+/// Records the rate-limit headers of a response to a request made with
+/// `credential`.
+pub(crate) fn record(credential: &str, res: &HttpResponse) {
+    let now = Utc::now().timestamp();
+    with_state(credential, |state| {
+        update(
+            state,
+            now,
+            res.status_code(),
+            header(res, "x-ratelimit-remaining"),
+            header(res, "x-ratelimit-reset"),
+            retry_after(res),
+        )
+    });
+}
@@ -0,0 +1,328 @@
+use crate::{log, settings};
+use std::{collections::HashMap, sync::OnceLock};
+
+static REDACTION_MODE: OnceLock<RedactionMode> = OnceLock::new();
+
+/// Prefixes of AWS access key IDs.
+const AWS_KEY_PREFIXES: &[&str] = &[
+    "AKIA", "ASIA", "ABIA", "ACCA", "AGPA", "AIDA", "AIPA", "ANPA", "ANVA", "AROA", "APKA",
+];
+
+/// Prefixes of GitHub personal access, OAuth, app and refresh tokens.
+const GITHUB_TOKEN_PREFIXES: &[&str] = &["ghp_", "gho_", "ghu_", "ghs_", "ghr_", "github_pat_"];
+
+/// Shortest token considered for the high-entropy check.
+const HIGH_ENTROPY_MIN_LENGTH: usize = 24;
+/// Shannon entropy, in bits per character, above which a token looks random.
+const HIGH_ENTROPY_BITS: f64 = 4.0;
+
+/// What to do when a secret is found, read from `REDACTION_MODE`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RedactionMode {
+    /// Replace each secret with a `[REDACTED:<category>]` marker.
+    Mask,
+    /// Reject the call.
+    Refuse,
+    Off,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(crate) enum SecretKind {
+    PrivateKey,
+    AwsAccessKey,
+    GithubToken,
+    Jwt,
+    Email,
+    HighEntropy,
+}
+
+impl SecretKind {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            SecretKind::PrivateKey => "private_key",
+            SecretKind::AwsAccessKey => "aws_access_key",
+            SecretKind::GithubToken => "github_token",
+            SecretKind::Jwt => "jwt",
+            SecretKind::Email => "email",
+            SecretKind::HighEntropy => "high_entropy",
+        }
+    }
+}
+
+pub(crate) fn mode() -> RedactionMode {
+    *REDACTION_MODE.get_or_init(|| {
+        match settings::get("REDACTION_MODE")
+            .map(|v| v.to_lowercase())
+            .as_deref()
+        {
+            Some("refuse") => RedactionMode::Refuse,
+            Some("off") => RedactionMode::Off,
+            _ => RedactionMode::Mask,
+        }
+    })
+}
+
+fn is_aws_access_key(token: &str) -> bool {
+    token.len() == 20
+        && AWS_KEY_PREFIXES.iter().any(|p| token.starts_with(p))
+        && token
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+}
+
+fn is_github_token(token: &str) -> bool {
+    GITHUB_TOKEN_PREFIXES.iter().any(|p| {
+        token.strip_prefix(p).is_some_and(|rest| {
+            rest.len() >= 20 && rest.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
+    })
+}
+
+fn is_jwt(token: &str) -> bool {
+    let parts: Vec<&str> = token.split('.').collect();
+    parts.len() == 3
+        && parts[0].starts_with("eyJ")
+        && parts[1].len() >= 4
+        && parts.iter().all(|part| {
+            !part.is_empty()
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        })
+}
+
+fn is_email(token: &str) -> bool {
+    let Some((local, domain)) = token.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && local
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "._%+-".contains(c))
+        && domain.split('.').all(|label| {
+            !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+        && domain.contains('.')
+        // Top-level domains start with a letter; `react@18.2.0` is a version
+        && domain
+            .rsplit('.')
+            .next()
+            .is_some_and(|tld| tld.starts_with(|c: char| c.is_ascii_alphabetic()))
+}
+
+/// Shannon entropy of `token` in bits per character.
+fn entropy(token: &str) -> f64 {
+    let mut counts: HashMap<char, usize> = HashMap::new();
+    for c in token.chars() {
+        *counts.entry(c).or_default() += 1;
+    }
+    let length = token.chars().count() as f64;
+    counts
+        .values()
+        .map(|&count| {
+            let p = count as f64 / length;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Splits a token into its camelCase words, digit runs and `+/=_-` separated
+/// parts, so `ReactDOMServer18` becomes `React`, `DOM`, `Server`, `18`.
+fn camel_words(token: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    for part in token.split(|c| "+/=_-".contains(c)) {
+        let bytes = part.as_bytes();
+        let mut start = 0;
+        for i in 1..bytes.len() {
+            let (prev, cur) = (bytes[i - 1], bytes[i]);
+            let next_lower = bytes.get(i + 1).is_some_and(u8::is_ascii_lowercase);
+            if prev.is_ascii_digit() != cur.is_ascii_digit()
+                || (prev.is_ascii_lowercase() && cur.is_ascii_uppercase())
+                || (prev.is_ascii_uppercase() && cur.is_ascii_uppercase() && next_lower)
+            {
+                words.push(&part[start..i]);
+                start = i;
+            }
+        }
+        if start < part.len() {
+            words.push(&part[start..]);
+        }
+    }
+    words
+}
+
+/// Whether a token reads as an identifier made of words, such as
+/// `webpack5ModuleFederationPlugin`: every word is a number, a short acronym
+/// or at least two letters, and the words average four letters or more.
+/// Random strings break into many one- and two-letter pieces instead.
+fn is_word_like(token: &str) -> bool {
+    let words = camel_words(token);
+    let letters: Vec<&str> = words
+        .iter()
+        .copied()
+        .filter(|w| !w.bytes().all(|b| b.is_ascii_digit()))
+        .collect();
+    let well_formed = letters.iter().all(|w| {
+        if w.bytes().all(|b| b.is_ascii_uppercase()) {
+            (2..=5).contains(&w.len())
+        } else {
+            w.len() >= 2
+        }
+    });
+    well_formed
+        && !letters.is_empty()
+        && letters.iter().map(|w| w.len()).sum::<usize>() >= 4 * letters.len()
+}
+
+/// Long tokens mixing letters and digits with near-random character
+/// distribution, such as API keys and secret access keys. Identifiers made of
+/// words are skipped even when their entropy is high.
+fn is_high_entropy(token: &str) -> bool {
+    token.len() >= HIGH_ENTROPY_MIN_LENGTH
+        && token
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "+/=_-".contains(c))
+        && token.chars().any(|c| c.is_ascii_digit())
+        && token.chars().any(|c| c.is_ascii_alphabetic())
+        && entropy(token) >= HIGH_ENTROPY_BITS
+        && !is_word_like(token)
+}
+
+fn classify(token: &str) -> Option<SecretKind> {
+    if is_aws_access_key(token) {
+        Some(SecretKind::AwsAccessKey)
+    } else if is_github_token(token) {
+        Some(SecretKind::GithubToken)
+    } else if is_jwt(token) {
+        Some(SecretKind::Jwt)
+    } else if is_email(token) {
+        Some(SecretKind::Email)
+    } else if is_high_entropy(token) {
+        Some(SecretKind::HighEntropy)
+    } else {
+        None
+    }
+}
+
+fn marker(kind: SecretKind) -> String {
+    format!("[REDACTED:{}]", kind.name())
+}
+
+/// Replaces PEM private key blocks, including an unterminated one at the end
+/// of the text.
+fn redact_private_keys(text: &str, found: &mut Vec<SecretKind>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find("-----BEGIN ") {
+        let label_start = start + "-----BEGIN ".len();
+        let is_private = rest[label_start..]
+            .find("-----")
+            .is_some_and(|end| rest[label_start..label_start + end].ends_with("PRIVATE KEY"));
+        if !is_private {
+            out.push_str(&rest[..label_start]);
+            rest = &rest[label_start..];
+            continue;
+        }
+
+        out.push_str(&rest[..start]);
+        out.push_str(&marker(SecretKind::PrivateKey));
+        found.push(SecretKind::PrivateKey);
+
+        let after = &rest[start..];
+        rest = match after.find("-----END ") {
+            Some(end) => {
+                let tail = &after[end + 9..];
+                match tail.find("-----") {
+                    Some(close) => &tail[close + 5..],
+                    None => "",
+                }
+            }
+            None => "",
+        };
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Masks secrets in `text`, returning the redacted text and the kinds found.
+/// Tokens are split on whitespace and stripped of surrounding quotes and
+/// punctuation before classification.
+pub(crate) fn redact(text: &str) -> (String, Vec<SecretKind>) {
+    let mut found = Vec::new();
+    let text = redact_private_keys(text, &mut found);
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text.as_str();
+    while !rest.is_empty() {
+        let word_start = rest
+            .find(|c: char| !c.is_whitespace())
+            .unwrap_or(rest.len());
+        out.push_str(&rest[..word_start]);
+        rest = &rest[word_start..];
+        let word_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let word = &rest[..word_end];
+        rest = &rest[word_end..];
+
+        let trim = |c: char| "\"'`()[]{}<>,;:!?".contains(c) || c == '.';
+        let token = word.trim_matches(trim);
+        match classify(token).filter(|_| !token.is_empty()) {
+            Some(kind) => {
+                let prefix_len = word.len() - word.trim_start_matches(trim).len();
+                out.push_str(&word[..prefix_len]);
+                out.push_str(&marker(kind));
+                out.push_str(&word[prefix_len + token.len()..]);
+                found.push(kind);
+            }
+            None => out.push_str(word),
+        }
+    }
+
+    found.sort();
+    found.dedup();
+    (out, found)
+}
+
+/// Applies the configured redaction mode to a tool argument in place. Returns
+/// an error in `refuse` mode when a secret is found. Found categories are
+/// logged, never the secrets themselves.
+pub(crate) fn apply(field: &str, value: &mut String) -> Result<(), String> {
+    let mode = mode();
+    if mode == RedactionMode::Off {
+        return Ok(());
+    }
+
+    let (redacted, found) = redact(value);
+    if found.is_empty() {
+        return Ok(());
+    }
+
+    let categories = found
+        .iter()
+        .map(|kind| kind.name())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let action = if mode == RedactionMode::Refuse {
+        "Refused"
+    } else {
+        "Redacted"
+    };
+    log::warning(
+        "redaction",
+        format!("{action} `{field}` containing: {categories}"),
+    )
+    .field("field", field)
+    .field(
+        "categories",
+        found.iter().map(|kind| kind.name()).collect::<Vec<_>>(),
+    )
+    .send();
+
+    if mode == RedactionMode::Refuse {
+        return Err(format!(
+            "`{field}` appears to contain sensitive data ({categories}). Remove it and try again."
+        ));
+    }
+    *value = redacted;
+    Ok(())
+}
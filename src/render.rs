@@ -0,0 +1,165 @@
+use crate::{settings, types::*};
+use std::{fmt::Write, sync::OnceLock};
+
+static RENDER_OPTIONS: OnceLock<RenderOptions> = OnceLock::new();
+
+/// How rendered documentation is laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MarkdownLayout {
+    /// Rules, code examples and documentation under their own headings, each
+    /// snippet with a title heading, source link and description.
+    Sections,
+    /// Snippets separated by rules with bold titles, without section headings,
+    /// sources or code descriptions.
+    Compact,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RenderOptions {
+    pub layout: MarkdownLayout,
+    pub include_sources: bool,
+}
+
+/// Reads the render options from `MARKDOWN_LAYOUT` and `MARKDOWN_SOURCES`.
+pub(crate) fn options() -> RenderOptions {
+    *RENDER_OPTIONS.get_or_init(|| RenderOptions {
+        layout: match settings::get("MARKDOWN_LAYOUT")
+            .map(|v| v.to_lowercase())
+            .as_deref()
+        {
+            Some("compact") => MarkdownLayout::Compact,
+            _ => MarkdownLayout::Sections,
+        },
+        include_sources: settings::flag("MARKDOWN_SOURCES", true),
+    })
+}
+
+/// Returns a backtick fence longer than any backtick run inside `code`.
+fn fence_for(code: &str) -> String {
+    let longest = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+pub(crate) fn write_code_block(out: &mut String, language: &str, code: &str) {
+    let fence = fence_for(code);
+    let _ = writeln!(out, "{fence}{}", language.trim());
+    let _ = writeln!(out, "{}", code.trim_end());
+    let _ = writeln!(out, "{fence}");
+}
+
+fn write_rules(out: &mut String, rules: &Rules, heading: Option<&str>) {
+    let all: Vec<&String> = rules
+        .global
+        .iter()
+        .chain(&rules.library_own)
+        .chain(&rules.library_team)
+        .collect();
+    if all.is_empty() {
+        return;
+    }
+    if let Some(heading) = heading {
+        let _ = writeln!(out, "{heading}\n");
+    }
+    for rule in all {
+        let _ = writeln!(out, "- {}", rule.trim());
+    }
+    out.push('\n');
+}
+
+fn code_title(snippet: &CodeSnippet) -> &str {
+    if snippet.code_title.trim().is_empty() {
+        snippet.page_title.trim()
+    } else {
+        snippet.code_title.trim()
+    }
+}
+
+fn info_title(snippet: &InfoSnippet) -> &str {
+    snippet
+        .breadcrumb
+        .as_deref()
+        .map(str::trim)
+        .filter(|b| !b.is_empty())
+        .unwrap_or("Documentation")
+}
+
+fn render_sections(response: &QueryDocsResponse, include_sources: bool) -> String {
+    let mut out = String::new();
+
+    if let Some(rules) = &response.rules {
+        write_rules(&mut out, rules, Some("## Rules"));
+    }
+
+    if !response.code_snippets.is_empty() {
+        out.push_str("## Code Examples\n\n");
+        for snippet in &response.code_snippets {
+            let _ = writeln!(out, "### {}\n", code_title(snippet));
+            if include_sources && !snippet.code_id.is_empty() {
+                let _ = writeln!(out, "Source: {}\n", snippet.code_id);
+            }
+            if !snippet.code_description.trim().is_empty() {
+                let _ = writeln!(out, "{}\n", snippet.code_description.trim());
+            }
+            for entry in &snippet.code_list {
+                write_code_block(&mut out, &entry.language, &entry.code);
+                out.push('\n');
+            }
+        }
+    }
+
+    if !response.info_snippets.is_empty() {
+        out.push_str("## Documentation\n\n");
+        for snippet in &response.info_snippets {
+            let _ = writeln!(out, "### {}\n", info_title(snippet));
+            if include_sources && let Some(page_id) = &snippet.page_id {
+                let _ = writeln!(out, "Source: {}\n", page_id);
+            }
+            let _ = writeln!(out, "{}\n", snippet.content.trim());
+        }
+    }
+
+    out.trim_end().to_string()
+}
+
+fn render_compact(response: &QueryDocsResponse) -> String {
+    let mut blocks = Vec::new();
+
+    if let Some(rules) = &response.rules {
+        let mut out = String::new();
+        write_rules(&mut out, rules, None);
+        if !out.is_empty() {
+            blocks.push(out);
+        }
+    }
+
+    for snippet in &response.code_snippets {
+        let mut out = String::new();
+        let _ = writeln!(out, "**{}**", code_title(snippet));
+        for entry in &snippet.code_list {
+            write_code_block(&mut out, &entry.language, &entry.code);
+        }
+        blocks.push(out);
+    }
+
+    for snippet in &response.info_snippets {
+        blocks.push(format!(
+            "**{}**\n{}\n",
+            info_title(snippet),
+            snippet.content.trim()
+        ));
+    }
+
+    blocks
+        .iter()
+        .map(|b| b.trim_end())
+        .collect::<Vec<_>>()
+        .join("\n\n---\n\n")
+}
+
+/// Renders a structured documentation response as Markdown.
+pub(crate) fn render_docs(response: &QueryDocsResponse, options: RenderOptions) -> String {
+    match options.layout {
+        MarkdownLayout::Sections => render_sections(response, options.include_sources),
+        MarkdownLayout::Compact => render_compact(response),
+    }
+}
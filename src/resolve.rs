@@ -0,0 +1,240 @@
+use crate::types::*;
+use std::fmt::Write;
+
+/// Points reported per criterion in `matchScore`. The maximum total is 100.
+/// Each criterion's maximum is worth more than all later ones combined, but
+/// the continuous criteria can still trade off within a sum, so results are
+/// ranked by comparing the criteria in order (see [`rank_cmp`]), not by total.
+const EXACT_NAME_POINTS: f64 = 69.0;
+const PARTIAL_NAME_POINTS: f64 = 34.0;
+const VERIFIED_POINTS: f64 = 16.0;
+const TRUST_POINTS: f64 = 8.0;
+const BENCHMARK_POINTS: f64 = 4.0;
+const SNIPPETS_POINTS: f64 = 2.0;
+const FINALIZED_POINTS: f64 = 1.0;
+
+/// Snippet count at which the snippet criterion is maxed out.
+const FULL_SNIPPETS: f64 = 10_000.0;
+
+/// Points within which a candidate counts as a close match to the best one.
+const CLOSE_MATCH_MARGIN: f64 = 10.0;
+/// Most candidates offered when disambiguating.
+const MAX_CLOSE_MATCHES: usize = 5;
+
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn name_match(library: &Library, library_name: &str) -> f64 {
+    let wanted = normalize_name(library_name);
+    if wanted.is_empty() {
+        return 0.0;
+    }
+
+    let title = normalize_name(&library.title);
+    let project = normalize_name(library.id.rsplit('/').next().unwrap_or_default());
+    if library.id.eq_ignore_ascii_case(library_name.trim()) || title == wanted || project == wanted
+    {
+        EXACT_NAME_POINTS
+    } else if title.contains(&wanted) || project.contains(&wanted) {
+        PARTIAL_NAME_POINTS
+    } else {
+        0.0
+    }
+}
+
+fn round(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+/// Computes the score breakdown for one library.
+fn score(library: &Library, library_name: &str) -> MatchScore {
+    let name_match = name_match(library, library_name);
+    let verified = if library.verified == Some(true) {
+        VERIFIED_POINTS
+    } else {
+        0.0
+    };
+    let trust = library.trust_score.unwrap_or_default().clamp(0.0, 10.0) / 10.0 * TRUST_POINTS;
+    let benchmark = library
+        .benchmark_score
+        .unwrap_or_default()
+        .clamp(0.0, 100.0)
+        / 100.0
+        * BENCHMARK_POINTS;
+    let snippets =
+        ((library.total_snippets.max(0.0) + 1.0).log10() / (FULL_SNIPPETS + 1.0).log10()).min(1.0)
+            * SNIPPETS_POINTS;
+    let state = if matches!(library.state, DocumentState::Finalized) {
+        FINALIZED_POINTS
+    } else {
+        0.0
+    };
+
+    MatchScore {
+        total: round(name_match + verified + trust + benchmark + snippets + state),
+        name_match: round(name_match),
+        verified: round(verified),
+        trust: round(trust),
+        benchmark: round(benchmark),
+        snippets: round(snippets),
+        state: round(state),
+    }
+}
+
+/// Orders two scores best first by name match, then verification, trust,
+/// benchmark, snippets and state, so a later criterion only breaks ties in the
+/// earlier ones.
+fn rank_cmp(a: &MatchScore, b: &MatchScore) -> std::cmp::Ordering {
+    let key = |s: &MatchScore| {
+        [
+            s.name_match,
+            s.verified,
+            s.trust,
+            s.benchmark,
+            s.snippets,
+            s.state,
+        ]
+    };
+    key(b)
+        .iter()
+        .zip(key(a).iter())
+        .map(|(b, a)| b.total_cmp(a))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(std::cmp::Ordering::Equal)
+}
+
+/// Scores every result against the searched name, sorts the results by
+/// [`rank_cmp`] (ties keep the API order) and records the top result as the
+/// best match.
+pub(crate) fn select_best_match(response: &mut ResolveLibraryIdResponse, library_name: &str) {
+    for library in &mut response.results {
+        library.match_score = Some(score(library, library_name));
+    }
+
+    response
+        .results
+        .sort_by(|a, b| match (&a.match_score, &b.match_score) {
+            (Some(a), Some(b)) => rank_cmp(a, b),
+            _ => std::cmp::Ordering::Equal,
+        });
+
+    response.best_match = response.results.first().map(|library| library.id.clone());
+}
+
+/// Returns the best match and the candidates scoring within
+/// [`CLOSE_MATCH_MARGIN`] of it, best first. Expects the results to be sorted
+/// by [`select_best_match`].
+pub(crate) fn close_matches(response: &ResolveLibraryIdResponse) -> Vec<&Library> {
+    let total = |library: &Library| library.match_score.as_ref().map(|s| s.total);
+    let Some(best) = response.results.first().and_then(total) else {
+        return Vec::new();
+    };
+    response
+        .results
+        .iter()
+        .take_while(|library| total(library).is_some_and(|t| best - t <= CLOSE_MATCH_MARGIN))
+        .take(MAX_CLOSE_MATCHES)
+        .collect()
+}
+
+/// Drops libraries that fail the `includeStates` (finalized only by default),
+/// `minTrustScore`, `minSnippets` and `verifiedOnly` filters.
+pub(crate) fn filter_libraries(
+    response: &mut ResolveLibraryIdResponse,
+    args: &ResolveLibraryIdArguments,
+) {
+    let default_states = [DocumentState::Finalized];
+    let states = args.include_states.as_deref().unwrap_or(&default_states);
+
+    let before = response.results.len();
+    response.results.retain(|library| {
+        states.contains(&library.state)
+            && args
+                .min_trust_score
+                .is_none_or(|min| library.trust_score.unwrap_or_default() >= min)
+            && args
+                .min_snippets
+                .is_none_or(|min| library.total_snippets >= min as f64)
+            && (args.verified_only != Some(true) || library.verified == Some(true))
+    });
+
+    let removed = (before - response.results.len()) as u32;
+    response.filtered_out = (removed > 0).then_some(removed);
+}
+
+fn versions(library: &Library) -> String {
+    if library.versions.is_empty() {
+        "-".to_string()
+    } else {
+        library.versions.join(", ")
+    }
+}
+
+fn trust(library: &Library) -> String {
+    library
+        .trust_score
+        .map_or_else(|| "-".to_string(), |t| t.to_string())
+}
+
+fn escape_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+/// Renders the text content of a `resolve_library_id` result in the requested
+/// format.
+pub(crate) fn format_libraries(
+    response: &ResolveLibraryIdResponse,
+    format: ResolveFormat,
+) -> String {
+    let mut out = String::new();
+
+    match format {
+        ResolveFormat::Json => return serde_json::to_string(response).unwrap_or_default(),
+        ResolveFormat::Compact => {
+            if let Some(best) = &response.best_match {
+                let _ = writeln!(out, "Best match: {best}");
+            }
+            for library in &response.results {
+                let _ = writeln!(
+                    out,
+                    "{} | {} | versions: {} | snippets: {} | trust: {}",
+                    library.id,
+                    library.title,
+                    versions(library),
+                    library.total_snippets,
+                    trust(library),
+                );
+            }
+        }
+        ResolveFormat::Markdown => {
+            if let Some(best) = &response.best_match {
+                let _ = writeln!(out, "**Best match:** `{best}`\n");
+            }
+            out.push_str("| ID | Title | Versions | Snippets | Trust |\n");
+            out.push_str("|----|-------|----------|----------|-------|\n");
+            for library in &response.results {
+                let _ = writeln!(
+                    out,
+                    "| `{}` | {} | {} | {} | {} |",
+                    library.id,
+                    escape_cell(&library.title),
+                    escape_cell(&versions(library)),
+                    library.total_snippets,
+                    trust(library),
+                );
+            }
+        }
+    }
+
+    if response.results.is_empty() {
+        out.push_str("No matching libraries found.\n");
+    }
+    if let Some(error) = &response.error {
+        let _ = writeln!(out, "Error: {error}");
+    }
+    out.trim_end().to_string()
+}
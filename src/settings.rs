@@ -0,0 +1,21 @@
+use extism_pdk::config;
+
+/// Returns the trimmed value of a config variable, or `None` when it is unset
+/// or empty.
+pub(crate) fn get(key: &str) -> Option<String> {
+    config::get(key)
+        .ok()
+        .flatten()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Reads a boolean config variable, accepting `true`/`false`, `yes`/`no` and
+/// `1`/`0`. Unset or unrecognized values fall back to `default`.
+pub(crate) fn flag(key: &str, default: bool) -> bool {
+    match get(key).map(|v| v.to_lowercase()).as_deref() {
+        Some("1" | "true" | "yes") => true,
+        Some("0" | "false" | "no") => false,
+        _ => default,
+    }
+}
@@ -0,0 +1,225 @@
+use crate::types::*;
+use std::collections::HashMap;
+
+/// Maps common language aliases to a single name so `ts` matches `typescript`.
+fn canonical_language(language: &str) -> String {
+    let language = language.trim().to_lowercase();
+    match language.as_str() {
+        "js" | "jsx" | "mjs" | "cjs" => "javascript",
+        "ts" | "tsx" | "mts" | "cts" => "typescript",
+        "py" | "python3" => "python",
+        "rs" => "rust",
+        "sh" | "shell" | "zsh" | "console" => "bash",
+        "yml" => "yaml",
+        "golang" => "go",
+        "c++" | "cc" | "cxx" | "hpp" => "cpp",
+        "c#" | "cs" => "csharp",
+        "kt" | "kts" => "kotlin",
+        "rb" => "ruby",
+        _ => return language,
+    }
+    .to_string()
+}
+
+fn keep_languages(snippet: &mut CodeSnippet, languages: &[String]) -> bool {
+    let matches = |language: &str| languages.contains(&canonical_language(language));
+
+    if snippet
+        .code_list
+        .iter()
+        .any(|entry| matches(&entry.language))
+    {
+        snippet.code_list.retain(|entry| matches(&entry.language));
+        true
+    } else {
+        matches(&snippet.code_language)
+    }
+}
+
+/// Applies the `languages`, `includeInfo`, `includeCode` and `maxSnippets`
+/// arguments to a documentation response.
+pub(crate) fn filter(response: &mut QueryDocsResponse, args: &QueryDocsArguments) {
+    if args.include_code == Some(false) {
+        response.code_snippets.clear();
+    }
+    if args.include_info == Some(false) {
+        response.info_snippets.clear();
+    }
+
+    if let Some(languages) = args.languages.as_ref().filter(|l| !l.is_empty()) {
+        let languages: Vec<String> = languages.iter().map(|l| canonical_language(l)).collect();
+        response
+            .code_snippets
+            .retain_mut(|snippet| keep_languages(snippet, &languages));
+    }
+
+    if let Some(max) = args.max_snippets {
+        response.code_snippets.truncate(max as usize);
+        response.info_snippets.truncate(max as usize);
+    }
+}
+
+/// Collapses all whitespace runs so formatting differences do not defeat
+/// duplicate detection.
+fn normalize_text(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Appends `addition` to a ` | `-separated list unless already present.
+fn merge_label(target: &mut String, addition: &str) {
+    let addition = addition.trim();
+    if addition.is_empty() || target.split(" | ").any(|label| label == addition) {
+        return;
+    }
+    if !target.is_empty() {
+        target.push_str(" | ");
+    }
+    target.push_str(addition);
+}
+
+fn code_content_key(snippet: &CodeSnippet) -> String {
+    snippet
+        .code_list
+        .iter()
+        .map(|entry| normalize_text(&entry.code))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Merges code snippets with the same `code_id` or code content, and info
+/// snippets with the same content, recording how many were merged.
+pub(crate) fn deduplicate(response: &mut QueryDocsResponse) {
+    let mut merged_code = 0u32;
+    let mut by_id: HashMap<String, usize> = HashMap::new();
+    let mut by_content: HashMap<String, usize> = HashMap::new();
+    let mut code_snippets: Vec<CodeSnippet> = Vec::with_capacity(response.code_snippets.len());
+
+    for snippet in response.code_snippets.drain(..) {
+        let content = code_content_key(&snippet);
+        let existing = by_id
+            .get(&snippet.code_id)
+            .filter(|_| !snippet.code_id.is_empty())
+            .or_else(|| {
+                by_content
+                    .get(&content)
+                    .filter(|_| !content.trim().is_empty())
+            })
+            .copied();
+
+        match existing {
+            Some(index) => {
+                merge_label(&mut code_snippets[index].page_title, &snippet.page_title);
+                merged_code += 1;
+            }
+            None => {
+                let index = code_snippets.len();
+                if !snippet.code_id.is_empty() {
+                    by_id.insert(snippet.code_id.clone(), index);
+                }
+                if !content.trim().is_empty() {
+                    by_content.insert(content, index);
+                }
+                code_snippets.push(snippet);
+            }
+        }
+    }
+    response.code_snippets = code_snippets;
+
+    let mut merged_info = 0u32;
+    let mut by_content: HashMap<String, usize> = HashMap::new();
+    let mut info_snippets: Vec<InfoSnippet> = Vec::with_capacity(response.info_snippets.len());
+
+    for snippet in response.info_snippets.drain(..) {
+        let content = normalize_text(&snippet.content);
+        match by_content.get(&content).filter(|_| !content.is_empty()) {
+            Some(&index) => {
+                if let Some(breadcrumb) = &snippet.breadcrumb {
+                    merge_label(
+                        info_snippets[index]
+                            .breadcrumb
+                            .get_or_insert_with(String::new),
+                        breadcrumb,
+                    );
+                }
+                merged_info += 1;
+            }
+            None => {
+                by_content.insert(content, info_snippets.len());
+                info_snippets.push(snippet);
+            }
+        }
+    }
+    response.info_snippets = info_snippets;
+
+    if merged_code > 0 || merged_info > 0 {
+        response.deduplicated = Some(DeduplicationStats {
+            code_snippets: merged_code,
+            info_snippets: merged_info,
+        });
+    }
+}
+
+fn response_tokens(response: &QueryDocsResponse) -> f64 {
+    response
+        .code_snippets
+        .iter()
+        .map(|s| s.code_tokens)
+        .chain(response.info_snippets.iter().map(|s| s.content_tokens))
+        .sum()
+}
+
+/// Keeps snippets, alternating between code and info in their original order,
+/// while they fit in `budget` tokens.
+pub(crate) fn trim_to_budget(response: &mut QueryDocsResponse, budget: f64) {
+    let mut used = 0.0;
+    let mut fits = |tokens: f64| {
+        if used + tokens <= budget {
+            used += tokens;
+            true
+        } else {
+            false
+        }
+    };
+
+    let mut keep_code = vec![false; response.code_snippets.len()];
+    let mut keep_info = vec![false; response.info_snippets.len()];
+    for i in 0..keep_code.len().max(keep_info.len()) {
+        if let Some(snippet) = response.code_snippets.get(i) {
+            keep_code[i] = fits(snippet.code_tokens);
+        }
+        if let Some(snippet) = response.info_snippets.get(i) {
+            keep_info[i] = fits(snippet.content_tokens);
+        }
+    }
+
+    let mut keep = keep_code.into_iter();
+    response
+        .code_snippets
+        .retain(|_| keep.next().unwrap_or(false));
+    let mut keep = keep_info.into_iter();
+    response
+        .info_snippets
+        .retain(|_| keep.next().unwrap_or(false));
+}
+
+/// Splits a shared token budget across libraries in proportion to the tokens
+/// each returned, trimming each library to its share. Nothing is trimmed when
+/// everything fits.
+pub(crate) fn allocate_budget(libraries: &mut [LibraryDocs], budget: f64) {
+    let total: f64 = libraries
+        .iter()
+        .filter_map(|library| library.docs.as_ref())
+        .map(response_tokens)
+        .sum();
+    if total <= budget {
+        return;
+    }
+
+    for library in libraries.iter_mut() {
+        if let Some(docs) = library.docs.as_mut() {
+            let share = (budget * response_tokens(docs) / total).floor();
+            trim_to_budget(docs, share);
+            library.token_budget = Some(share as u32);
+        }
+    }
+}
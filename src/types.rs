@@ -1,3 +1,4 @@
+use crate::{api_key::KeySource, cache::CacheKey, errors::ToolError};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::hash::{Hash, Hasher};
@@ -20,6 +21,49 @@ pub(crate) struct ResolveLibraryIdArguments {
     )]
     pub query: String,
 
+    #[schemars(
+        description = "Maximum number of candidate libraries to return, best matches first. All candidates are returned if omitted."
+    )]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub limit: Option<u32>,
+
+    #[schemars(
+        description = "Library states to include. Defaults to ['finalized'], which drops libraries whose documentation \
+        is being deleted, failed to build or has not been processed yet."
+    )]
+    #[serde(rename = "includeStates")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub include_states: Option<Vec<DocumentState>>,
+
+    #[schemars(description = "Only return libraries with at least this trust score (0-10).")]
+    #[serde(rename = "minTrustScore")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub min_trust_score: Option<f64>,
+
+    #[schemars(description = "Only return libraries with at least this many code snippets.")]
+    #[serde(rename = "minSnippets")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub min_snippets: Option<u32>,
+
+    #[schemars(description = "Only return verified libraries. Defaults to false.")]
+    #[serde(rename = "verifiedOnly")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub verified_only: Option<bool>,
+
+    #[schemars(
+        description = "Format of the text content. 'json' (default) returns the full response as JSON, 'compact' returns \
+        one line per library (ID, title, versions, snippet count, trust score), and 'markdown' returns a table with the \
+        same columns. The full structured content is returned in every format."
+    )]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub format: Option<ResolveFormat>,
+
     #[schemars(description = "Optional Context7 API key to use for authenticated access.")]
     #[serde(rename = "context7ApiKey")]
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -27,15 +71,44 @@ pub(crate) struct ResolveLibraryIdArguments {
     pub context7_api_key: Option<String>,
 }
 
+#[derive(Debug, Clone, Copy, Hash, Serialize, Deserialize, JsonSchema)]
+pub(crate) enum ResolveFormat {
+    #[serde(rename = "json")]
+    Json,
+    #[serde(rename = "compact")]
+    Compact,
+    #[serde(rename = "markdown")]
+    Markdown,
+}
+
 /// Hash excludes `context7_api_key` so cache lookups are key-agnostic.
 impl Hash for ResolveLibraryIdArguments {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.library_name.hash(state);
         self.query.hash(state);
+        self.limit.hash(state);
+        self.include_states.hash(state);
+        self.min_trust_score.map(f64::to_bits).hash(state);
+        self.min_snippets.hash(state);
+        self.verified_only.hash(state);
+        self.format.hash(state);
     }
 }
 
-#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema)]
+impl CacheKey for ResolveLibraryIdArguments {
+    fn query(&self) -> &str {
+        &self.query
+    }
+
+    fn with_query(&self, query: String) -> Self {
+        Self {
+            query,
+            ..self.clone()
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub(crate) enum DocumentState {
     Delete,
@@ -74,6 +147,24 @@ pub(crate) struct Library {
     pub vip: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub verified: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "matchScore")]
+    #[serde(default)]
+    pub match_score: Option<MatchScore>,
+}
+
+/// Plugin-computed score used to pick the best match, with the points
+/// contributed by each criterion. The total is out of 100.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct MatchScore {
+    pub total: f64,
+    #[serde(rename = "nameMatch")]
+    pub name_match: f64,
+    pub verified: f64,
+    pub trust: f64,
+    pub benchmark: f64,
+    pub snippets: f64,
+    pub state: f64,
 }
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -81,6 +172,31 @@ pub(crate) struct ResolveLibraryIdResponse {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
     pub results: Vec<Library>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "bestMatch")]
+    #[serde(default)]
+    pub best_match: Option<String>,
+    /// Number of libraries removed by the state, trust, snippet and
+    /// verification filters.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "filteredOut")]
+    #[serde(default)]
+    pub filtered_out: Option<u32>,
+    /// Number of libraries removed by the `LIBRARY_ALLOW`, `LIBRARY_DENY` and
+    /// `MIN_TRUST_SCORE` policy.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "blockedByPolicy")]
+    #[serde(default)]
+    pub blocked_by_policy: Option<u32>,
+    /// Set when the user picked the library from close matches.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "selectedByUser")]
+    #[serde(default)]
+    pub selected_by_user: Option<bool>,
+    /// Configured alias that resolved the library without an API search.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub alias: Option<String>,
 }
 
 // --- query_docs request/response types ---
@@ -107,6 +223,9 @@ pub(crate) struct CodeSnippet {
     pub page_title: String,
     #[serde(rename = "codeList")]
     pub code_list: Vec<CodeListEntry>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub relevance: Option<f64>,
 }
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -119,6 +238,9 @@ pub(crate) struct InfoSnippet {
     pub content: String,
     #[serde(rename = "contentTokens")]
     pub content_tokens: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub relevance: Option<f64>,
 }
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -133,6 +255,15 @@ pub(crate) struct Rules {
     pub library_team: Vec<String>,
 }
 
+/// Number of duplicate snippets merged into others by `query_docs`.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct DeduplicationStats {
+    #[serde(rename = "codeSnippets")]
+    pub code_snippets: u32,
+    #[serde(rename = "infoSnippets")]
+    pub info_snippets: u32,
+}
+
 #[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct QueryDocsResponse {
     #[serde(rename = "codeSnippets")]
@@ -141,6 +272,9 @@ pub(crate) struct QueryDocsResponse {
     pub info_snippets: Vec<InfoSnippet>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub rules: Option<Rules>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub deduplicated: Option<DeduplicationStats>,
 }
 
 #[allow(dead_code)]
@@ -153,6 +287,8 @@ pub(crate) enum QueryDocsType {
     Text,
     #[serde(rename = "json")]
     Json,
+    #[serde(rename = "both")]
+    Both,
 }
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -175,7 +311,8 @@ pub(crate) struct QueryDocsArguments {
 
     #[schemars(
         description = "The response format type. Use 'text' to receive markdown in the text content, \
-        or 'json' to receive structured JSON in the structured content. If omitted \
+        'json' to receive structured JSON in the structured content, or 'both' to receive markdown \
+        in the text content and structured JSON in the structured content. If omitted \
         structured content is returned."
     )]
     #[serde(rename = "type")]
@@ -183,6 +320,46 @@ pub(crate) struct QueryDocsArguments {
     #[serde(default)]
     pub r#type: Option<QueryDocsType>,
 
+    #[schemars(
+        description = "Only return code examples in these languages (e.g., ['rust'], ['typescript', 'javascript']). \
+        Matched case-insensitively against each snippet's language and the languages of its code blocks."
+    )]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub languages: Option<Vec<String>>,
+
+    #[schemars(
+        description = "Whether to include documentation (info) snippets. Defaults to true."
+    )]
+    #[serde(rename = "includeInfo")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub include_info: Option<bool>,
+
+    #[schemars(description = "Whether to include code snippets. Defaults to true.")]
+    #[serde(rename = "includeCode")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub include_code: Option<bool>,
+
+    #[schemars(
+        description = "Maximum number of code snippets and of info snippets to return (applied to each list)."
+    )]
+    #[serde(rename = "maxSnippets")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub max_snippets: Option<u32>,
+
+    #[schemars(
+        description = "Re-rank snippets locally by relevance to the query (BM25 over titles, descriptions, \
+        breadcrumbs and content). Each snippet gets a 'relevance' score from 0 to 1 and snippets are sorted by it, \
+        so 'maxSnippets' keeps the most relevant ones. With type 'text', the text is rendered locally from the \
+        re-ranked structured response. Defaults to false."
+    )]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub rerank: Option<bool>,
+
     #[schemars(description = "Optional Context7 API key to use for authenticated access.")]
     #[serde(rename = "context7ApiKey")]
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -190,11 +367,274 @@ pub(crate) struct QueryDocsArguments {
     pub context7_api_key: Option<String>,
 }
 
+impl QueryDocsArguments {
+    /// Whether any snippet filter is set. Filtering needs the structured
+    /// response, so the text is then rendered locally.
+    pub fn has_snippet_filters(&self) -> bool {
+        self.languages.as_ref().is_some_and(|l| !l.is_empty())
+            || self.include_info == Some(false)
+            || self.include_code == Some(false)
+            || self.max_snippets.is_some()
+    }
+
+    /// Whether the text must be rendered locally from the structured response,
+    /// because snippet filters or re-ranking change it.
+    pub fn renders_locally(&self) -> bool {
+        self.has_snippet_filters() || self.rerank == Some(true)
+    }
+}
+
 /// Hash excludes `context7_api_key` so cache lookups are key-agnostic.
 impl Hash for QueryDocsArguments {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.library_id.hash(state);
         self.query.hash(state);
         self.r#type.hash(state);
+        self.languages.hash(state);
+        self.include_info.hash(state);
+        self.include_code.hash(state);
+        self.max_snippets.hash(state);
+        self.rerank.hash(state);
+    }
+}
+
+impl CacheKey for QueryDocsArguments {
+    fn query(&self) -> &str {
+        &self.query
+    }
+
+    fn with_query(&self, query: String) -> Self {
+        Self {
+            query,
+            ..self.clone()
+        }
+    }
+}
+
+// --- query_docs_multi request/response types ---
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct QueryDocsMultiArguments {
+    #[schemars(
+        description = "Exact Context7-compatible library IDs (e.g., ['/tokio-rs/axum', '/launchbadge/sqlx']) retrieved from \
+        'resolve_library_id' or directly from user query in the format '/org/project' or '/org/project/version'."
+    )]
+    #[serde(rename = "libraryIds")]
+    pub library_ids: Vec<String>,
+
+    #[schemars(
+        description = "The question or task you need help with, spanning the given libraries. Be specific and include relevant \
+        details. The query is sent to the Context7 API for processing. Do not include any sensitive or confidential information \
+        such as API keys, passwords, credentials, personal data, or proprietary code in your query."
+    )]
+    pub query: String,
+
+    #[schemars(
+        description = "Optional total token budget shared by all libraries. Each library receives a share proportional to the \
+        amount of documentation it returned."
+    )]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub tokens: Option<u32>,
+
+    #[schemars(
+        description = "Re-rank each library's snippets locally by relevance to the query before the token budget is \
+        applied, so the most relevant snippets are kept. Defaults to false."
+    )]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub rerank: Option<bool>,
+
+    #[schemars(description = "Optional Context7 API key to use for authenticated access.")]
+    #[serde(rename = "context7ApiKey")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub context7_api_key: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct LibraryDocs {
+    #[serde(rename = "libraryId")]
+    pub library_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub docs: Option<QueryDocsResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ToolError>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "tokenBudget")]
+    pub token_budget: Option<u32>,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct QueryDocsMultiResponse {
+    pub libraries: Vec<LibraryDocs>,
+}
+
+// --- answer_from_docs request/response types ---
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct AnswerFromDocsArguments {
+    #[schemars(
+        description = "Exact Context7-compatible library ID (e.g., '/vercel/next.js') retrieved from 'resolve_library_id' \
+        or directly from user query in the format '/org/project' or '/org/project/version'."
+    )]
+    #[serde(rename = "libraryId")]
+    pub library_id: String,
+
+    #[schemars(
+        description = "The question to answer from the library's documentation. Be specific. The query is sent to the \
+        Context7 API for processing. Do not include any sensitive or confidential information such as API keys, passwords, \
+        credentials, personal data, or proprietary code in your query."
+    )]
+    pub query: String,
+
+    #[schemars(
+        description = "Maximum number of code snippets and of info snippets to answer from. Defaults to all returned snippets."
+    )]
+    #[serde(rename = "maxSnippets")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub max_snippets: Option<u32>,
+
+    #[schemars(description = "Maximum length of the answer in tokens. Defaults to 1024.")]
+    #[serde(rename = "maxTokens")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+
+    #[schemars(description = "Optional Context7 API key to use for authenticated access.")]
+    #[serde(rename = "context7ApiKey")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub context7_api_key: Option<String>,
+}
+
+/// A snippet cited by an answer.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct Citation {
+    /// The snippet's `codeId` or `pageId`.
+    pub id: String,
+    pub title: String,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct AnswerFromDocsResponse {
+    #[serde(rename = "libraryId")]
+    pub library_id: String,
+    pub answer: String,
+    pub citations: Vec<Citation>,
+    /// Model that wrote the answer, as reported by the client.
+    pub model: String,
+    /// Number of snippets the answer was written from.
+    #[serde(rename = "snippetsUsed")]
+    pub snippets_used: u32,
+}
+
+// --- context7_status request/response types ---
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct StatusArguments {
+    #[schemars(
+        description = "Optional Context7 API key to check instead of the configured one. It is never echoed back."
+    )]
+    #[serde(rename = "context7ApiKey")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub context7_api_key: Option<String>,
+}
+
+/// Outcome of the authenticated test request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum AuthStatus {
+    /// The key was accepted.
+    Valid,
+    /// No key was used and the anonymous request succeeded.
+    Anonymous,
+    /// The key was rejected with 401 or 403.
+    Rejected,
+    /// The request was rate limited.
+    RateLimited,
+    /// The API returned another error status.
+    Error,
+    /// The API could not be reached.
+    Unreachable,
+}
+
+/// Rate-limit headers of the test response, as sent by the API.
+#[derive(Default, Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct RateLimitHeaders {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remaining: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reset: Option<String>,
+    #[serde(rename = "retryAfter")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct StatusResponse {
+    /// Where the key came from; absent when no key was resolved.
+    #[serde(rename = "keySource")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_source: Option<KeySource>,
+    /// 1-based position of the key among the configured keys.
+    #[serde(rename = "keyPosition")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_position: Option<usize>,
+    /// Number of configured keys.
+    #[serde(rename = "configuredKeys")]
+    pub configured_keys: usize,
+    #[serde(rename = "authStatus")]
+    pub auth_status: AuthStatus,
+    /// HTTP status of the test request, when it got a response.
+    #[serde(rename = "httpStatus")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http_status: Option<u16>,
+    #[serde(rename = "rateLimit")]
+    pub rate_limit: RateLimitHeaders,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(rename = "pluginVersion")]
+    pub plugin_version: String,
+    #[serde(rename = "baseUrl")]
+    pub base_url: String,
+}
+
+// --- get_library_info request types ---
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct GetLibraryInfoArguments {
+    #[schemars(
+        description = "Exact Context7-compatible library ID (e.g., '/mongodb/docs', '/vercel/next.js'). A version suffix such as '/vercel/next.js/v14.3.0' is accepted; the library's record lists all of its versions."
+    )]
+    #[serde(rename = "libraryId")]
+    pub library_id: String,
+
+    #[schemars(description = "Optional Context7 API key to use for authenticated access.")]
+    #[serde(rename = "context7ApiKey")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub context7_api_key: Option<String>,
+}
+
+/// Hash excludes `context7_api_key` so cache lookups are key-agnostic.
+impl Hash for GetLibraryInfoArguments {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.library_id.hash(state);
+    }
+}
+
+/// Library lookups have no query; every entry is keyed by its library ID.
+impl CacheKey for GetLibraryInfoArguments {
+    fn query(&self) -> &str {
+        ""
+    }
+
+    fn with_query(&self, _query: String) -> Self {
+        self.clone()
     }
 }
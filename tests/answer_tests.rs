@@ -0,0 +1,205 @@
+use std::fmt::Write;
+
+// ---------------------------------------------------------------------------
+// Minimal query_docs response replicas
+// ---------------------------------------------------------------------------
+
+#[derive(Default, Debug, Clone)]
+struct CodeListEntry {
+    language: String,
+    code: String,
+}
+
+#[derive(Default, Debug, Clone)]
+struct CodeSnippet {
+    code_title: String,
+    code_description: String,
+    code_id: String,
+    code_list: Vec<CodeListEntry>,
+}
+
+#[derive(Default, Debug, Clone)]
+struct InfoSnippet {
+    page_id: Option<String>,
+    breadcrumb: Option<String>,
+    content: String,
+}
+
+#[derive(Default, Debug, Clone)]
+struct QueryDocsResponse {
+    code_snippets: Vec<CodeSnippet>,
+    info_snippets: Vec<InfoSnippet>,
+}
+
+// ---------------------------------------------------------------------------
+// Replicated helpers from answer.rs and render.rs
+// ---------------------------------------------------------------------------
+
+fn fence_for(code: &str) -> String {
+    let longest = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+fn write_code_block(out: &mut String, language: &str, code: &str) {
+    let fence = fence_for(code);
+    let _ = writeln!(out, "{fence}{}", language.trim());
+    let _ = writeln!(out, "{}", code.trim_end());
+    let _ = writeln!(out, "{fence}");
+}
+
+struct Excerpt {
+    id: String,
+    title: String,
+    body: String,
+}
+
+fn excerpts(docs: &QueryDocsResponse) -> Vec<Excerpt> {
+    let code = docs.code_snippets.iter().enumerate().map(|(i, s)| {
+        let mut body = String::new();
+        if !s.code_description.trim().is_empty() {
+            let _ = writeln!(body, "{}", s.code_description.trim());
+        }
+        for entry in &s.code_list {
+            write_code_block(&mut body, &entry.language, &entry.code);
+        }
+        Excerpt {
+            id: Some(s.code_id.trim())
+                .filter(|id| !id.is_empty())
+                .map_or_else(|| format!("code-{}", i + 1), str::to_string),
+            title: s.code_title.clone(),
+            body,
+        }
+    });
+    let info = docs.info_snippets.iter().enumerate().map(|(i, s)| Excerpt {
+        id: s
+            .page_id
+            .as_deref()
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map_or_else(|| format!("info-{}", i + 1), str::to_string),
+        title: s.breadcrumb.clone().unwrap_or_default(),
+        body: s.content.clone(),
+    });
+    code.chain(info).collect()
+}
+
+fn context(excerpts: &[Excerpt], max_chars: usize) -> (String, usize) {
+    let mut out = String::new();
+    let mut included = 0;
+    for excerpt in excerpts {
+        let heading = format!("[{}] {}", excerpt.id, excerpt.title.trim());
+        let block = format!("{}\n{}\n\n", heading.trim_end(), excerpt.body.trim_end());
+        if included > 0 && out.len() + block.len() > max_chars {
+            break;
+        }
+        out.push_str(&block);
+        included += 1;
+    }
+    (out.trim_end().to_string(), included)
+}
+
+fn citations<'a>(answer: &str, excerpts: &'a [Excerpt]) -> Vec<&'a Excerpt> {
+    let mut cited: Vec<&Excerpt> = Vec::new();
+    let mut rest = answer;
+    while let Some(start) = rest.find('[') {
+        rest = &rest[start + 1..];
+        let Some(end) = rest.find(']') else { break };
+        for id in rest[..end].split(',').map(str::trim) {
+            if let Some(excerpt) = excerpts.iter().find(|e| e.id == id)
+                && !cited.iter().any(|c| c.id == excerpt.id)
+            {
+                cited.push(excerpt);
+            }
+        }
+        rest = &rest[end + 1..];
+    }
+    cited
+}
+
+// ---------------------------------------------------------------------------
+// Helpers to build test data
+// ---------------------------------------------------------------------------
+
+fn docs() -> QueryDocsResponse {
+    QueryDocsResponse {
+        code_snippets: vec![
+            CodeSnippet {
+                code_title: "Create a router".to_string(),
+                code_description: "Build a router with one route.".to_string(),
+                code_id: "abc123".to_string(),
+                code_list: vec![CodeListEntry {
+                    language: "rust".to_string(),
+                    code: "let app = Router::new();".to_string(),
+                }],
+            },
+            CodeSnippet {
+                code_title: "Untitled".to_string(),
+                ..Default::default()
+            },
+        ],
+        info_snippets: vec![
+            InfoSnippet {
+                page_id: Some("https://docs.rs/axum/routing".to_string()),
+                breadcrumb: Some("Routing > Basics".to_string()),
+                content: "Routes are matched in order.".to_string(),
+            },
+            InfoSnippet {
+                content: "No page ID.".to_string(),
+                ..Default::default()
+            },
+        ],
+    }
+}
+
+// ===========================================================================
+// Tests
+// ===========================================================================
+
+#[test]
+fn test_excerpt_ids_and_fallbacks() {
+    let excerpts = excerpts(&docs());
+    let ids: Vec<&str> = excerpts.iter().map(|e| e.id.as_str()).collect();
+    assert_eq!(
+        ids,
+        ["abc123", "code-2", "https://docs.rs/axum/routing", "info-2"]
+    );
+    assert_eq!(excerpts[2].title, "Routing > Basics");
+    assert!(
+        excerpts[0]
+            .body
+            .starts_with("Build a router with one route.\n```rust\n")
+    );
+}
+
+#[test]
+fn test_context_labels_excerpts_and_respects_limit() {
+    let excerpts = excerpts(&docs());
+    let (text, included) = context(&excerpts, usize::MAX);
+    assert_eq!(included, 4);
+    assert!(text.starts_with("[abc123] Create a router\nBuild a router"));
+    assert!(text.contains("[info-2]\nNo page ID."));
+
+    // The first excerpt is always included, even when it alone is too long
+    let (text, included) = context(&excerpts, 10);
+    assert_eq!(included, 1);
+    assert!(text.starts_with("[abc123]"));
+}
+
+#[test]
+fn test_citations_in_order_deduplicated_and_known_only() {
+    let excerpts = excerpts(&docs());
+    let answer = "Use `Router::new()` [abc123]. Routes match in order \
+                  [https://docs.rs/axum/routing, abc123]. See also [unknown] and [info-2].";
+    let ids: Vec<&str> = citations(answer, &excerpts)
+        .iter()
+        .map(|e| e.id.as_str())
+        .collect();
+    assert_eq!(ids, ["abc123", "https://docs.rs/axum/routing", "info-2"]);
+}
+
+#[test]
+fn test_citations_limited_to_sent_excerpts() {
+    let excerpts = excerpts(&docs());
+    assert!(citations("From [info-2].", &excerpts[..2]).is_empty());
+    assert!(citations("No citations here [", &excerpts).is_empty());
+}
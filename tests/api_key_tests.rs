@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// ---------------------------------------------------------------------------
+// Replicated key pool helpers from api_key.rs
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct KeyringEntryId {
+    service: String,
+    user: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum KeyEntry {
+    Plain(String),
+    Keyring(KeyringEntryId),
+}
+
+fn parse_entry(value: &str) -> Option<KeyEntry> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+    Some(match serde_json::from_str::<KeyringEntryId>(value) {
+        Ok(entry_id) => KeyEntry::Keyring(entry_id),
+        Err(_) => KeyEntry::Plain(value.to_string()),
+    })
+}
+
+fn parse_entries(value: &str) -> Vec<KeyEntry> {
+    match serde_json::from_str::<Vec<Value>>(value) {
+        Ok(items) => items
+            .into_iter()
+            .filter_map(|item| match item {
+                Value::String(key) => parse_entry(&key),
+                Value::Object(_) => serde_json::from_value(item).ok().map(KeyEntry::Keyring),
+                _ => None,
+            })
+            .collect(),
+        Err(_) => value.split(',').filter_map(parse_entry).collect(),
+    }
+}
+
+fn next_available(
+    cooling_until: &[Option<i64>],
+    start: usize,
+    now: i64,
+    exclude: Option<usize>,
+) -> Option<usize> {
+    let len = cooling_until.len();
+    (0..len)
+        .map(|offset| (start + offset) % len)
+        .find(|&i| Some(i) != exclude && cooling_until[i].is_none_or(|until| until <= now))
+}
+
+// ---------------------------------------------------------------------------
+// Helpers to build test data
+// ---------------------------------------------------------------------------
+
+fn plain(key: &str) -> KeyEntry {
+    KeyEntry::Plain(key.to_string())
+}
+
+fn keyring(service: &str, user: &str) -> KeyEntry {
+    KeyEntry::Keyring(KeyringEntryId {
+        service: service.to_string(),
+        user: user.to_string(),
+    })
+}
+
+const NOW: i64 = 1_700_000_000;
+
+// ===========================================================================
+// Tests
+// ===========================================================================
+
+#[test]
+fn test_single_entry_plain_or_keyring() {
+    assert_eq!(parse_entry(" ctx7sk-abc "), Some(plain("ctx7sk-abc")));
+    assert_eq!(
+        parse_entry(r#"{"service":"context7","user":"team"}"#),
+        Some(keyring("context7", "team"))
+    );
+    assert_eq!(parse_entry("  "), None);
+}
+
+#[test]
+fn test_entries_json_array_mixes_plain_and_keyring() {
+    let entries = parse_entries(
+        r#"["ctx7sk-one", {"service":"context7","user":"two"}, "{\"service\":\"context7\",\"user\":\"three\"}", "", 4]"#,
+    );
+    assert_eq!(
+        entries,
+        [
+            plain("ctx7sk-one"),
+            keyring("context7", "two"),
+            keyring("context7", "three"),
+        ]
+    );
+}
+
+#[test]
+fn test_entries_comma_separated() {
+    assert_eq!(
+        parse_entries("ctx7sk-one, ctx7sk-two,,"),
+        [plain("ctx7sk-one"), plain("ctx7sk-two")]
+    );
+}
+
+#[test]
+fn test_failover_prefers_first_available_key() {
+    let cooling = [None, None, None];
+    assert_eq!(next_available(&cooling, 0, NOW, None), Some(0));
+
+    // The first key is rate limited: move to the next one
+    let cooling = [Some(NOW + 30), None, None];
+    assert_eq!(next_available(&cooling, 0, NOW, None), Some(1));
+
+    // Its cooldown has passed: back to the first key
+    let cooling = [Some(NOW - 1), None, None];
+    assert_eq!(next_available(&cooling, 0, NOW, None), Some(0));
+}
+
+#[test]
+fn test_round_robin_wraps_and_skips_cooling_keys() {
+    let cooling = [None, Some(NOW + 30), None];
+    assert_eq!(next_available(&cooling, 1, NOW, None), Some(2));
+    assert_eq!(next_available(&cooling, 3 % 3, NOW, None), Some(0));
+}
+
+#[test]
+fn test_rotation_excludes_current_key() {
+    assert_eq!(next_available(&[None, None], 0, NOW, Some(0)), Some(1));
+    assert_eq!(next_available(&[None], 0, NOW, Some(0)), None);
+    assert_eq!(
+        next_available(&[None, Some(NOW + 5)], 0, NOW, Some(0)),
+        None
+    );
+    assert_eq!(next_available(&[], 0, NOW, None), None);
+}
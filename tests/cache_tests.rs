@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
+use std::collections::HashSet;
 use std::collections::hash_map::DefaultHasher;
 use std::fs;
 use std::hash::{Hash, Hasher};
@@ -246,6 +247,60 @@ fn cache_clear(cache_dir: &Path) -> (u64, Vec<String>) {
     (removed, errors)
 }
 
+// ---------------------------------------------------------------------------
+// Replicated query normalization and near-match helpers from cache.rs
+// ---------------------------------------------------------------------------
+
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "can", "do", "does", "for", "from", "how",
+    "i", "in", "is", "it", "my", "of", "on", "or", "the", "to", "what", "when", "with",
+];
+
+fn normalize_query(query: &str) -> String {
+    query
+        .to_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '+' || c == '#' {
+                c
+            } else {
+                ' '
+            }
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .filter(|word| !STOPWORDS.contains(word))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn query_similarity(a: &str, b: &str) -> f64 {
+    let a: HashSet<&str> = a.split_whitespace().collect();
+    let b: HashSet<&str> = b.split_whitespace().collect();
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    #[serde(rename = "cacheKey")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    key: Option<CacheEntryKey>,
+
+    #[serde(flatten)]
+    result: CallToolResult,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntryKey {
+    variant: String,
+    query: String,
+}
+
 // ---------------------------------------------------------------------------
 // Helper to build simple CallToolResult values for testing
 // ---------------------------------------------------------------------------
@@ -977,3 +1032,91 @@ fn test_cache_get_returns_none_for_wrong_json_shape() {
     let cached = cache_get(dir.path(), "query_docs", &args, ttl);
     assert!(cached.is_none(), "JSON with wrong shape should return None");
 }
+
+// --- Query normalization ---
+
+#[test]
+fn test_normalize_query_folds_case_and_punctuation() {
+    assert_eq!(
+        normalize_query("  React   useEffect, cleanup!! "),
+        "react useeffect cleanup"
+    );
+}
+
+#[test]
+fn test_normalize_query_removes_stopwords() {
+    assert_eq!(
+        normalize_query("How to use the useEffect cleanup function in React"),
+        "use useeffect cleanup function react"
+    );
+}
+
+#[test]
+fn test_normalize_query_keeps_language_symbols() {
+    assert_eq!(normalize_query("C++ and C# interop"), "c++ c# interop");
+}
+
+#[test]
+fn test_normalized_variants_share_cache_path() {
+    let dir = TempDir::new().unwrap();
+    let a = QueryDocsArguments {
+        library_id: "/facebook/react".to_string(),
+        query: normalize_query("React useEffect cleanup"),
+        context7_api_key: None,
+    };
+    let b = QueryDocsArguments {
+        library_id: "/facebook/react".to_string(),
+        query: normalize_query("react  useEffect cleanup?"),
+        context7_api_key: None,
+    };
+    assert_eq!(
+        cache_path(dir.path(), "query_docs", &a),
+        cache_path(dir.path(), "query_docs", &b)
+    );
+}
+
+// --- Near-match similarity ---
+
+#[test]
+fn test_query_similarity_identical() {
+    assert_eq!(query_similarity("react useeffect", "react useeffect"), 1.0);
+}
+
+#[test]
+fn test_query_similarity_partial_overlap() {
+    let a = normalize_query("react useEffect cleanup");
+    let b = normalize_query("React useEffect cleanup function");
+    assert_eq!(query_similarity(&a, &b), 0.75);
+}
+
+#[test]
+fn test_query_similarity_disjoint_and_empty() {
+    assert_eq!(query_similarity("react hooks", "tokio runtime"), 0.0);
+    assert_eq!(query_similarity("", ""), 0.0);
+}
+
+// --- Cache entry format ---
+
+#[test]
+fn test_cache_entry_reads_as_call_tool_result() {
+    let entry = CacheEntry {
+        key: Some(CacheEntryKey {
+            variant: "abc123".to_string(),
+            query: "react hooks".to_string(),
+        }),
+        result: make_structured_result("text", "key", "value"),
+    };
+    let json = serde_json::to_string(&entry).unwrap();
+
+    let result: CallToolResult = serde_json::from_str(&json).unwrap();
+    assert_eq!(result, make_structured_result("text", "key", "value"));
+}
+
+#[test]
+fn test_legacy_cache_file_reads_as_entry() {
+    let json = serde_json::to_string(&make_text_result("legacy")).unwrap();
+
+    let entry: CacheEntry = serde_json::from_str(&json).unwrap();
+    assert!(entry.key.is_none());
+    assert_eq!(entry.result, make_text_result("legacy"));
+}
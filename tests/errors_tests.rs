@@ -0,0 +1,227 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use std::fmt;
+
+// ---------------------------------------------------------------------------
+// Minimal CallToolResult replica (text content only)
+// ---------------------------------------------------------------------------
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+struct CallToolResult {
+    #[serde(rename = "_meta")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    meta: Option<Map<String, Value>>,
+    content: Vec<String>,
+    #[serde(rename = "isError")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    is_error: Option<bool>,
+    #[serde(rename = "structuredContent")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    structured_content: Option<Map<String, Value>>,
+}
+
+impl CallToolResult {
+    fn error(error: String) -> CallToolResult {
+        CallToolResult {
+            is_error: Some(true),
+            content: vec![error],
+            ..Default::default()
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Replicated error model from errors.rs
+// ---------------------------------------------------------------------------
+
+/// What went wrong, so callers can branch on the kind instead of matching
+/// message text.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+enum ErrorKind {
+    /// The tool arguments are missing, malformed or out of range.
+    InvalidArguments,
+    /// The library or documentation does not exist.
+    NotFound,
+    /// The API key is missing, invalid or lacks access.
+    Unauthorized,
+    /// The request was rate limited. `retryAfter` is in seconds, when known.
+    RateLimited {
+        #[serde(rename = "retryAfter")]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(default)]
+        retry_after: Option<u64>,
+    },
+    /// The API returned an unexpected status.
+    Upstream { status: u16 },
+    /// The API could not be reached.
+    Network,
+    /// The request was blocked by the library policy or query redaction.
+    PolicyDenied,
+    /// A response could not be parsed or converted.
+    Deserialization,
+    /// A local failure, such as a cache I/O error.
+    Internal,
+}
+
+/// A tool failure with its kind and a human-readable message. Returned as the
+/// text content of an error result and as `_meta.error`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ToolError {
+    #[serde(flatten)]
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl fmt::Display for ToolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl ToolError {
+    fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    fn invalid_arguments(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::InvalidArguments, message)
+    }
+
+    fn policy_denied(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::PolicyDenied, message)
+    }
+
+    fn network(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Network, message)
+    }
+
+    fn deserialization(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Deserialization, message)
+    }
+
+    fn internal(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Internal, message)
+    }
+
+    /// Classifies a non-success HTTP status from the Context7 API.
+    fn from_status(status: u16, retry_after: Option<u64>, message: impl Into<String>) -> Self {
+        let kind = match status {
+            400 | 422 => ErrorKind::InvalidArguments,
+            401 | 403 => ErrorKind::Unauthorized,
+            404 => ErrorKind::NotFound,
+            429 => ErrorKind::RateLimited { retry_after },
+            _ => ErrorKind::Upstream { status },
+        };
+        Self::new(kind, message)
+    }
+
+    /// Recovers the error from a result converted from a `ToolError`.
+    fn from_result(result: &CallToolResult) -> Option<Self> {
+        let error = result.meta.as_ref()?.get("error")?;
+        serde_json::from_value(error.clone()).ok()
+    }
+}
+
+/// Builds an error `CallToolResult` carrying the message as text and the typed
+/// error as `_meta.error`. Structured content is left unset, since the error
+/// does not match the tool's output schema.
+impl From<ToolError> for CallToolResult {
+    fn from(error: ToolError) -> Self {
+        let mut result = CallToolResult::error(error.message.clone());
+        if let Ok(value) = serde_json::to_value(&error) {
+            result
+                .meta
+                .get_or_insert_with(Map::new)
+                .insert("error".to_string(), value);
+        }
+        result
+    }
+}
+
+// ===========================================================================
+// Tests
+// ===========================================================================
+
+#[test]
+fn test_status_classification() {
+    let kind = |status| ToolError::from_status(status, None, "").kind;
+    assert_eq!(kind(400), ErrorKind::InvalidArguments);
+    assert_eq!(kind(401), ErrorKind::Unauthorized);
+    assert_eq!(kind(403), ErrorKind::Unauthorized);
+    assert_eq!(kind(404), ErrorKind::NotFound);
+    assert_eq!(kind(429), ErrorKind::RateLimited { retry_after: None });
+    assert_eq!(kind(500), ErrorKind::Upstream { status: 500 });
+    assert_eq!(kind(418), ErrorKind::Upstream { status: 418 });
+}
+
+#[test]
+fn test_serialized_shape() {
+    let error = ToolError::from_status(429, Some(30), "slow down");
+    assert_eq!(
+        serde_json::to_value(&error).unwrap(),
+        json!({"kind": "rateLimited", "retryAfter": 30, "message": "slow down"})
+    );
+
+    let error = ToolError::from_status(502, None, "bad gateway");
+    assert_eq!(
+        serde_json::to_value(&error).unwrap(),
+        json!({"kind": "upstream", "status": 502, "message": "bad gateway"})
+    );
+
+    let error = ToolError::policy_denied("blocked");
+    assert_eq!(
+        serde_json::to_value(&error).unwrap(),
+        json!({"kind": "policyDenied", "message": "blocked"})
+    );
+
+    let error = ToolError::from_status(429, None, "");
+    assert!(
+        serde_json::to_value(&error)
+            .unwrap()
+            .get("retryAfter")
+            .is_none()
+    );
+}
+
+#[test]
+fn test_error_result_carries_message_and_meta_error() {
+    let result: CallToolResult =
+        ToolError::invalid_arguments("Invalid arguments: missing field `query`").into();
+
+    assert_eq!(result.is_error, Some(true));
+    assert_eq!(
+        result.content,
+        vec!["Invalid arguments: missing field `query`"]
+    );
+    assert_eq!(
+        result.meta.as_ref().unwrap()["error"]["kind"],
+        "invalidArguments"
+    );
+    // Error results do not carry structured content, which would not match
+    // the tool's output schema
+    assert!(result.structured_content.is_none());
+    let json = serde_json::to_value(&result).unwrap();
+    assert!(json.get("structuredContent").is_none());
+    assert_eq!(json["_meta"]["error"]["message"], json!(result.content[0]));
+}
+
+#[test]
+fn test_error_round_trips_through_result() {
+    for error in [
+        ToolError::network("down"),
+        ToolError::deserialization("bad json"),
+        ToolError::internal("disk full"),
+        ToolError::from_status(401, None, "no key"),
+        ToolError::from_status(429, Some(5), "limited"),
+        ToolError::from_status(503, None, "unavailable"),
+    ] {
+        let result: CallToolResult = error.clone().into();
+        assert_eq!(ToolError::from_result(&result), Some(error));
+    }
+
+    assert!(ToolError::from_result(&CallToolResult::error("plain".to_string())).is_none());
+}
@@ -0,0 +1,222 @@
+use htmd::{
+    HtmlToMarkdown,
+    options::{BulletListMarker, Options},
+};
+use url::Url;
+
+// ---------------------------------------------------------------------------
+// Replicated helpers from html.rs so they can be tested without the PDK
+// runtime.
+// ---------------------------------------------------------------------------
+
+/// Tags whose presence marks a field as HTML rather than Markdown that merely
+/// mentions angle brackets (e.g. `Vec<String>`).
+const HTML_TAGS: &[&str] = &[
+    "a",
+    "b",
+    "blockquote",
+    "br",
+    "code",
+    "div",
+    "em",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "i",
+    "img",
+    "li",
+    "ol",
+    "p",
+    "pre",
+    "span",
+    "strong",
+    "table",
+    "td",
+    "th",
+    "tr",
+    "ul",
+];
+
+/// Query parameters added by analytics and newsletter tooling.
+const TRACKING_PARAMS: &[&str] = &["fbclid", "gclid", "mc_cid", "mc_eid", "ref_src", "msclkid"];
+
+/// Link texts used for heading permalinks.
+const ANCHOR_TEXTS: &[&str] = &["", "#", "¶", "§", "🔗", "link"];
+
+/// Returns true when `text` contains at least one opening or closing tag from
+/// [`HTML_TAGS`].
+fn looks_like_html(text: &str) -> bool {
+    text.match_indices('<').any(|(i, _)| {
+        let rest = text[i + 1..].trim_start_matches('/');
+        let name: String = rest
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        let after = rest[name.len()..].chars().next();
+        HTML_TAGS.contains(&name.as_str()) && matches!(after, Some('>' | ' ' | '/' | '\n' | '\t'))
+    })
+}
+
+fn strip_tracking(url: &str) -> String {
+    let Ok(mut parsed) = Url::parse(url) else {
+        return url.to_string();
+    };
+    if parsed.query().is_none() {
+        return url.to_string();
+    }
+    let kept: Vec<(String, String)> = parsed
+        .query_pairs()
+        .filter(|(k, _)| !k.starts_with("utm_") && !TRACKING_PARAMS.contains(&k.as_ref()))
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect();
+    if kept.is_empty() {
+        parsed.set_query(None);
+    } else {
+        parsed.query_pairs_mut().clear().extend_pairs(kept);
+    }
+    parsed.to_string()
+}
+
+/// Parses a Markdown inline link at the start of `s`, returning its text, URL
+/// and byte length.
+fn parse_link(s: &str) -> Option<(&str, &str, usize)> {
+    let text_end = s.find("](")?;
+    let text = &s[1..text_end];
+    if text.contains(['[', ']', '\n']) {
+        return None;
+    }
+    let url_start = text_end + 2;
+    let url_len = s[url_start..].find(')')?;
+    let url = &s[url_start..url_start + url_len];
+    if url.contains(char::is_whitespace) {
+        return None;
+    }
+    Some((text, url, url_start + url_len + 1))
+}
+
+/// Drops heading permalinks (`[#](#section)`) and removes tracking parameters
+/// from the remaining links.
+fn strip_link_noise(markdown: &str) -> String {
+    let mut out = String::with_capacity(markdown.len());
+    let mut rest = markdown;
+
+    while let Some(start) = rest.find('[') {
+        out.push_str(&rest[..start]);
+        let candidate = &rest[start..];
+        match parse_link(candidate) {
+            Some((text, url, len)) => {
+                let is_anchor = url.starts_with('#') && ANCHOR_TEXTS.contains(&text.trim());
+                if !is_anchor {
+                    out.push('[');
+                    out.push_str(text);
+                    out.push_str("](");
+                    out.push_str(&strip_tracking(url));
+                    out.push(')');
+                }
+                rest = &candidate[len..];
+            }
+            None => {
+                out.push('[');
+                rest = &candidate[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+
+    out.replace('¶', "").trim().to_string()
+}
+
+/// Converts an HTML fragment to Markdown, returning `None` if conversion fails.
+fn html_to_markdown(html: &str) -> Option<String> {
+    let converter = HtmlToMarkdown::builder()
+        .options(Options {
+            bullet_list_marker: BulletListMarker::Dash,
+            ..Default::default()
+        })
+        .skip_tags(vec!["script", "style", "noscript", "svg", "button"])
+        .build();
+    converter.convert(html).ok().map(|md| strip_link_noise(&md))
+}
+
+// ===========================================================================
+// Tests
+// ===========================================================================
+
+// --- HTML detection ---
+
+#[test]
+fn test_looks_like_html_detects_tags() {
+    assert!(looks_like_html("<p>Hello</p>"));
+    assert!(looks_like_html("Use <code>useEffect</code> for effects"));
+    assert!(looks_like_html("line one<br/>line two"));
+    assert!(looks_like_html("<a href=\"https://example.com\">docs</a>"));
+}
+
+#[test]
+fn test_looks_like_html_ignores_generics_and_comparisons() {
+    assert!(!looks_like_html("Returns a `Vec<String>` of names"));
+    assert!(!looks_like_html("Accepts Option<T> where T: Clone"));
+    assert!(!looks_like_html("if a < b && b > c"));
+    assert!(!looks_like_html("Plain markdown with **bold** text"));
+}
+
+// --- Link noise ---
+
+#[test]
+fn test_strip_link_noise_removes_permalinks() {
+    assert_eq!(
+        strip_link_noise("## Setup [#](#setup)\nText"),
+        "## Setup \nText"
+    );
+    assert_eq!(strip_link_noise("## Usage [¶](#usage)"), "## Usage");
+}
+
+#[test]
+fn test_strip_link_noise_removes_tracking_params() {
+    assert_eq!(
+        strip_link_noise("See [docs](https://example.com/page?utm_source=x&utm_medium=y)"),
+        "See [docs](https://example.com/page)"
+    );
+    assert_eq!(
+        strip_link_noise("See [docs](https://example.com/page?tab=api&gclid=123)"),
+        "See [docs](https://example.com/page?tab=api)"
+    );
+}
+
+#[test]
+fn test_strip_link_noise_keeps_regular_links_and_brackets() {
+    let markdown = "Call [the API](https://example.com/api) with arr[0] and [x]";
+    assert_eq!(strip_link_noise(markdown), markdown);
+}
+
+// --- Conversion ---
+
+#[test]
+fn test_html_to_markdown_converts_fragment() {
+    let md = html_to_markdown(
+        "<p>Use <strong>hooks</strong> inside <code>components</code>.</p><ul><li>One</li><li>Two</li></ul>",
+    )
+    .unwrap();
+    assert!(md.contains("**hooks**"), "{md}");
+    assert!(md.contains("`components`"), "{md}");
+    assert!(
+        md.lines()
+            .any(|line| line.starts_with('-') && line.ends_with("One")),
+        "{md}"
+    );
+}
+
+#[test]
+fn test_html_to_markdown_drops_heading_anchors() {
+    let md = html_to_markdown(
+        "<h2>Install<a class=\"anchor\" href=\"#install\">#</a></h2><p>Run it.</p>",
+    )
+    .unwrap();
+    assert!(md.starts_with("## Install"), "{md}");
+    assert!(!md.contains("(#install)"), "{md}");
+}
@@ -0,0 +1,253 @@
+use std::fmt;
+use url::Url;
+
+// ---------------------------------------------------------------------------
+// Replicated parser from library_id.rs
+// ---------------------------------------------------------------------------
+
+/// Hosts whose repository URLs map directly onto `/org/project` IDs.
+const REPOSITORY_HOSTS: &[&str] = &["github.com", "gitlab.com", "context7.com"];
+
+/// A Context7 library ID of the form `/org/project[/version]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LibraryId {
+    pub org: String,
+    pub project: String,
+    pub version: Option<String>,
+}
+
+impl fmt::Display for LibraryId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "/{}/{}", self.org, self.project)?;
+        if let Some(version) = &self.version {
+            write!(f, "/{version}")?;
+        }
+        Ok(())
+    }
+}
+
+fn invalid(input: &str, reason: impl fmt::Display) -> String {
+    format!(
+        "Invalid library ID '{input}': {reason}. Library IDs have the form '/org/project' or \
+         '/org/project/version' (e.g. '/vercel/next.js'); use resolve_library_id to look one up."
+    )
+}
+
+/// Extracts the `org/project` path from a GitHub, GitLab or Context7 URL.
+/// Trailing parts such as `/tree/main` or GitLab's `/-/blob/...` are dropped,
+/// as is a `.git` suffix.
+fn path_from_url(input: &str) -> Result<Vec<String>, String> {
+    let with_scheme = if input.contains("://") {
+        input.to_string()
+    } else {
+        format!("https://{input}")
+    };
+    let url = Url::parse(&with_scheme).map_err(|e| invalid(input, e))?;
+    let host = url
+        .host_str()
+        .unwrap_or_default()
+        .trim_start_matches("www.");
+    if !REPOSITORY_HOSTS.contains(&host) {
+        return Err(invalid(
+            input,
+            format_args!("URLs from '{host}' are not supported, only GitHub, GitLab and Context7"),
+        ));
+    }
+
+    let segments: Vec<String> = url
+        .path_segments()
+        .into_iter()
+        .flatten()
+        .take_while(|segment| *segment != "-")
+        .filter(|segment| !segment.is_empty())
+        .take(2)
+        .map(|segment| segment.trim_end_matches(".git").to_string())
+        .collect();
+    Ok(segments)
+}
+
+fn is_url(input: &str) -> bool {
+    input.contains("://")
+        || REPOSITORY_HOSTS.iter().any(|host| {
+            input.starts_with(&format!("{host}/")) || input.starts_with(&format!("www.{host}/"))
+        })
+}
+
+fn check_segment(input: &str, segment: &str) -> Result<(), String> {
+    if segment == "." || segment == ".." {
+        return Err(invalid(
+            input,
+            format_args!("'{segment}' is not a valid path segment"),
+        ));
+    }
+    match segment
+        .chars()
+        .find(|c| !(c.is_alphanumeric() || matches!(c, '-' | '_' | '.')))
+    {
+        Some(c) if c.is_whitespace() => Err(invalid(input, "it contains whitespace")),
+        Some(c) => Err(invalid(
+            input,
+            format_args!("'{c}' is not allowed in '{segment}'"),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Parses and normalizes a library ID. GitHub, GitLab and Context7 URLs are
+/// converted to IDs, and missing leading or extra trailing slashes are fixed.
+/// Errors name the problem and, where possible, suggest a valid ID.
+fn parse(input: &str) -> Result<LibraryId, String> {
+    let trimmed = input.trim();
+    if trimmed.is_empty() {
+        return Err(invalid(input, "it is empty"));
+    }
+
+    let segments: Vec<String> = if is_url(trimmed) {
+        path_from_url(trimmed)?
+    } else {
+        trimmed
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+
+    match segments.as_slice() {
+        [] => Err(invalid(input, "it has no org or project")),
+        [name] => Err(invalid(
+            input,
+            format_args!(
+                "'{name}' is missing its org; call resolve_library_id with libraryName '{name}' to find the ID"
+            ),
+        )),
+        [org, project, rest @ ..] => {
+            if let [version, _, ..] = rest {
+                return Err(invalid(
+                    input,
+                    format_args!(
+                        "it has too many path segments; did you mean '/{org}/{project}/{version}'?"
+                    ),
+                ));
+            }
+            for segment in &segments {
+                check_segment(input, segment)?;
+            }
+            Ok(LibraryId {
+                org: org.clone(),
+                project: project.clone(),
+                version: rest.first().cloned(),
+            })
+        }
+    }
+}
+
+// ===========================================================================
+// Tests
+// ===========================================================================
+
+fn normalized(input: &str) -> String {
+    parse(input)
+        .unwrap_or_else(|e| panic!("{input}: {e}"))
+        .to_string()
+}
+
+#[test]
+fn test_valid_ids_are_unchanged() {
+    assert_eq!(normalized("/vercel/next.js"), "/vercel/next.js");
+    assert_eq!(
+        normalized("/vercel/next.js/v15.1.8"),
+        "/vercel/next.js/v15.1.8"
+    );
+    assert_eq!(normalized("/websites/react_dev"), "/websites/react_dev");
+}
+
+#[test]
+fn test_parsed_parts() {
+    let id = parse("/mongodb/docs/v7.0").unwrap();
+    assert_eq!(id.org, "mongodb");
+    assert_eq!(id.project, "docs");
+    assert_eq!(id.version.as_deref(), Some("v7.0"));
+    assert!(parse("/mongodb/docs").unwrap().version.is_none());
+}
+
+#[test]
+fn test_slashes_and_whitespace_are_normalized() {
+    assert_eq!(normalized("vercel/next.js"), "/vercel/next.js");
+    assert_eq!(normalized("/vercel/next.js/"), "/vercel/next.js");
+    assert_eq!(normalized("  //vercel//next.js//  "), "/vercel/next.js");
+}
+
+#[test]
+fn test_repository_urls_are_normalized() {
+    assert_eq!(
+        normalized("https://github.com/vercel/next.js"),
+        "/vercel/next.js"
+    );
+    assert_eq!(
+        normalized("https://github.com/vercel/next.js.git"),
+        "/vercel/next.js"
+    );
+    assert_eq!(
+        normalized("https://github.com/vercel/next.js/tree/canary/docs"),
+        "/vercel/next.js"
+    );
+    assert_eq!(normalized("github.com/facebook/react"), "/facebook/react");
+    assert_eq!(
+        normalized("https://www.github.com/facebook/react/"),
+        "/facebook/react"
+    );
+    assert_eq!(
+        normalized("https://gitlab.com/gitlab-org/gitlab/-/blob/master/README.md"),
+        "/gitlab-org/gitlab"
+    );
+    assert_eq!(
+        normalized("https://context7.com/vercel/next.js"),
+        "/vercel/next.js"
+    );
+}
+
+#[test]
+fn test_unsupported_url_host() {
+    let err = parse("https://example.com/vercel/next.js").unwrap_err();
+    assert!(err.contains("'example.com' are not supported"), "{err}");
+}
+
+#[test]
+fn test_missing_org_suggests_resolve() {
+    let err = parse("react").unwrap_err();
+    assert!(err.contains("'react' is missing its org"), "{err}");
+    assert!(
+        err.contains("resolve_library_id with libraryName 'react'"),
+        "{err}"
+    );
+
+    let err = parse("https://github.com/vercel").unwrap_err();
+    assert!(err.contains("'vercel' is missing its org"), "{err}");
+}
+
+#[test]
+fn test_too_many_segments_suggests_id() {
+    let err = parse("/vercel/next.js/v15.1.8/docs/routing").unwrap_err();
+    assert!(
+        err.contains("did you mean '/vercel/next.js/v15.1.8'?"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_invalid_characters() {
+    let err = parse("/vercel/next js").unwrap_err();
+    assert!(err.contains("whitespace"), "{err}");
+
+    let err = parse("@next/core").unwrap_err();
+    assert!(err.contains("'@' is not allowed in '@next'"), "{err}");
+
+    let err = parse("/vercel/..").unwrap_err();
+    assert!(err.contains("'..' is not a valid path segment"), "{err}");
+}
+
+#[test]
+fn test_empty_id() {
+    assert!(parse("").unwrap_err().contains("it is empty"));
+    assert!(parse("///").unwrap_err().contains("no org or project"));
+}
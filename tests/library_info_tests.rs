@@ -0,0 +1,149 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::time::Duration;
+
+// ---------------------------------------------------------------------------
+// Replicated library lookup helpers from lib.rs, policy.rs and cache.rs
+// ---------------------------------------------------------------------------
+
+const DEFAULT_CACHE_DAYS: u64 = 1;
+const DEFAULT_LIBRARY_INFO_CACHE_HOURS: u64 = 6;
+const LIBRARY_INFO_TOOL: &str = "get_library_info";
+
+#[derive(Debug, Clone)]
+struct Library {
+    id: String,
+    title: String,
+}
+
+#[derive(Debug, Clone)]
+struct GetLibraryInfoArguments {
+    library_id: String,
+    context7_api_key: Option<String>,
+}
+
+impl Hash for GetLibraryInfoArguments {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.library_id.hash(state);
+    }
+}
+
+fn base_id(library_id: &str) -> String {
+    let trimmed = library_id.trim().trim_matches('/').to_lowercase();
+    let base: Vec<&str> = trimmed.splitn(3, '/').take(2).collect();
+    format!("/{}", base.join("/"))
+}
+
+/// The search term used to find a library: its org and project.
+fn search_term(library_id: &str) -> String {
+    base_id(library_id)
+        .trim_start_matches('/')
+        .replace('/', " ")
+}
+
+fn exact_match(library_id: &str, results: Vec<Library>) -> Option<Library> {
+    let base_id = base_id(library_id);
+    results
+        .into_iter()
+        .find(|library| self::base_id(&library.id) == base_id)
+}
+
+fn ttl_for(tool_name: &str, cache_ttl: Option<&str>, library_info_ttl: Option<&str>) -> Duration {
+    if tool_name == LIBRARY_INFO_TOOL {
+        let hours = library_info_ttl
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_LIBRARY_INFO_CACHE_HOURS);
+        Duration::from_secs(hours * 60 * 60)
+    } else {
+        let days = cache_ttl
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_CACHE_DAYS);
+        Duration::from_secs(days * 24 * 60 * 60)
+    }
+}
+
+fn hash_hex<T: Hash>(value: &T) -> String {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    format!("{:x}", hasher.finish())
+}
+
+// ---------------------------------------------------------------------------
+// Helpers to build test data
+// ---------------------------------------------------------------------------
+
+fn library(id: &str, title: &str) -> Library {
+    Library {
+        id: id.to_string(),
+        title: title.to_string(),
+    }
+}
+
+fn search_results() -> Vec<Library> {
+    vec![
+        library("/vercel/next.js-examples", "Next.js Examples"),
+        library("/websites/nextjs", "Next.js Website"),
+        library("/vercel/next.js", "Next.js"),
+    ]
+}
+
+// ===========================================================================
+// Tests
+// ===========================================================================
+
+#[test]
+fn test_search_term_is_org_and_project() {
+    assert_eq!(search_term("/vercel/next.js"), "vercel next.js");
+    assert_eq!(search_term("/vercel/next.js/v14.3.0"), "vercel next.js");
+    // Generic project names are searched together with their org
+    assert_eq!(search_term("mongodb/docs/"), "mongodb docs");
+    assert_eq!(search_term("/websites/React_Dev"), "websites react_dev");
+}
+
+#[test]
+fn test_exact_match_ignores_case_and_version() {
+    let found = exact_match("/Vercel/Next.js/v15.1.8", search_results()).unwrap();
+    assert_eq!(found.id, "/vercel/next.js");
+    assert_eq!(found.title, "Next.js");
+}
+
+#[test]
+fn test_similar_ids_do_not_match() {
+    assert!(exact_match("/vercel/next", search_results()).is_none());
+    assert!(exact_match("/nextjs/next.js", search_results()).is_none());
+    assert!(exact_match("/vercel/next.js", Vec::new()).is_none());
+}
+
+#[test]
+fn test_library_info_ttl_is_separate_and_in_hours() {
+    assert_eq!(
+        ttl_for(LIBRARY_INFO_TOOL, Some("7"), None),
+        Duration::from_secs(6 * 60 * 60)
+    );
+    assert_eq!(
+        ttl_for(LIBRARY_INFO_TOOL, None, Some("1")),
+        Duration::from_secs(60 * 60)
+    );
+    assert_eq!(
+        ttl_for("query_docs", Some("7"), Some("1")),
+        Duration::from_secs(7 * 24 * 60 * 60)
+    );
+    assert_eq!(
+        ttl_for(LIBRARY_INFO_TOOL, None, Some("soon")),
+        Duration::from_secs(6 * 60 * 60)
+    );
+}
+
+#[test]
+fn test_cache_key_ignores_api_key() {
+    let anonymous = GetLibraryInfoArguments {
+        library_id: "/vercel/next.js".to_string(),
+        context7_api_key: None,
+    };
+    let keyed = GetLibraryInfoArguments {
+        context7_api_key: Some("ctx7sk-abc".to_string()),
+        ..anonymous.clone()
+    };
+    assert!(keyed.context7_api_key.is_some());
+    assert_eq!(hash_hex(&anonymous), hash_hex(&keyed));
+}
@@ -0,0 +1,790 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt::Write};
+
+// ---------------------------------------------------------------------------
+// Duplicated types from types.rs that the library selection helpers use.
+// ---------------------------------------------------------------------------
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum DocumentState {
+    Delete,
+    Error,
+    Finalized,
+    #[default]
+    Initial,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+struct Library {
+    pub id: String,
+    pub title: String,
+    #[serde(rename = "totalSnippets")]
+    pub total_snippets: f64,
+    pub state: DocumentState,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "trustScore")]
+    pub trust_score: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "benchmarkScore")]
+    pub benchmark_score: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verified: Option<bool>,
+    #[serde(default)]
+    pub versions: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "matchScore")]
+    #[serde(default)]
+    pub match_score: Option<MatchScore>,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+struct MatchScore {
+    pub total: f64,
+    #[serde(rename = "nameMatch")]
+    pub name_match: f64,
+    pub verified: f64,
+    pub trust: f64,
+    pub benchmark: f64,
+    pub snippets: f64,
+    pub state: f64,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+struct ResolveLibraryIdResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub results: Vec<Library>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "bestMatch")]
+    #[serde(default)]
+    pub best_match: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "filteredOut")]
+    #[serde(default)]
+    pub filtered_out: Option<u32>,
+}
+
+#[derive(Default, Debug, Clone)]
+struct ResolveLibraryIdArguments {
+    pub include_states: Option<Vec<DocumentState>>,
+    pub min_trust_score: Option<f64>,
+    pub min_snippets: Option<u32>,
+    pub verified_only: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ResolveFormat {
+    Json,
+    Compact,
+    Markdown,
+}
+
+// ---------------------------------------------------------------------------
+// Replicated helpers from resolve.rs
+// ---------------------------------------------------------------------------
+
+/// Points reported per criterion in `matchScore`. The maximum total is 100.
+/// Each criterion's maximum is worth more than all later ones combined, but
+/// the continuous criteria can still trade off within a sum, so results are
+/// ranked by comparing the criteria in order (see [`rank_cmp`]), not by total.
+const EXACT_NAME_POINTS: f64 = 69.0;
+const PARTIAL_NAME_POINTS: f64 = 34.0;
+const VERIFIED_POINTS: f64 = 16.0;
+const TRUST_POINTS: f64 = 8.0;
+const BENCHMARK_POINTS: f64 = 4.0;
+const SNIPPETS_POINTS: f64 = 2.0;
+const FINALIZED_POINTS: f64 = 1.0;
+
+/// Snippet count at which the snippet criterion is maxed out.
+const FULL_SNIPPETS: f64 = 10_000.0;
+
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn name_match(library: &Library, library_name: &str) -> f64 {
+    let wanted = normalize_name(library_name);
+    if wanted.is_empty() {
+        return 0.0;
+    }
+
+    let title = normalize_name(&library.title);
+    let project = normalize_name(library.id.rsplit('/').next().unwrap_or_default());
+    if library.id.eq_ignore_ascii_case(library_name.trim()) || title == wanted || project == wanted
+    {
+        EXACT_NAME_POINTS
+    } else if title.contains(&wanted) || project.contains(&wanted) {
+        PARTIAL_NAME_POINTS
+    } else {
+        0.0
+    }
+}
+
+fn round(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+/// Computes the score breakdown for one library.
+fn score(library: &Library, library_name: &str) -> MatchScore {
+    let name_match = name_match(library, library_name);
+    let verified = if library.verified == Some(true) {
+        VERIFIED_POINTS
+    } else {
+        0.0
+    };
+    let trust = library.trust_score.unwrap_or_default().clamp(0.0, 10.0) / 10.0 * TRUST_POINTS;
+    let benchmark = library
+        .benchmark_score
+        .unwrap_or_default()
+        .clamp(0.0, 100.0)
+        / 100.0
+        * BENCHMARK_POINTS;
+    let snippets =
+        ((library.total_snippets.max(0.0) + 1.0).log10() / (FULL_SNIPPETS + 1.0).log10()).min(1.0)
+            * SNIPPETS_POINTS;
+    let state = if matches!(library.state, DocumentState::Finalized) {
+        FINALIZED_POINTS
+    } else {
+        0.0
+    };
+
+    MatchScore {
+        total: round(name_match + verified + trust + benchmark + snippets + state),
+        name_match: round(name_match),
+        verified: round(verified),
+        trust: round(trust),
+        benchmark: round(benchmark),
+        snippets: round(snippets),
+        state: round(state),
+    }
+}
+
+/// Orders two scores best first by name match, then verification, trust,
+/// benchmark, snippets and state, so a later criterion only breaks ties in the
+/// earlier ones.
+fn rank_cmp(a: &MatchScore, b: &MatchScore) -> std::cmp::Ordering {
+    let key = |s: &MatchScore| {
+        [
+            s.name_match,
+            s.verified,
+            s.trust,
+            s.benchmark,
+            s.snippets,
+            s.state,
+        ]
+    };
+    key(b)
+        .iter()
+        .zip(key(a).iter())
+        .map(|(b, a)| b.total_cmp(a))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(std::cmp::Ordering::Equal)
+}
+
+/// Scores every result against the searched name, sorts the results by
+/// [`rank_cmp`] (ties keep the API order) and records the top result as the
+/// best match.
+pub(crate) fn select_best_match(response: &mut ResolveLibraryIdResponse, library_name: &str) {
+    for library in &mut response.results {
+        library.match_score = Some(score(library, library_name));
+    }
+
+    response
+        .results
+        .sort_by(|a, b| match (&a.match_score, &b.match_score) {
+            (Some(a), Some(b)) => rank_cmp(a, b),
+            _ => std::cmp::Ordering::Equal,
+        });
+
+    response.best_match = response.results.first().map(|library| library.id.clone());
+}
+
+const CLOSE_MATCH_MARGIN: f64 = 10.0;
+const MAX_CLOSE_MATCHES: usize = 5;
+
+/// Returns the best match and the candidates scoring within
+/// [`CLOSE_MATCH_MARGIN`] of it, best first.
+fn close_matches(response: &ResolveLibraryIdResponse) -> Vec<&Library> {
+    let total = |library: &Library| library.match_score.as_ref().map(|s| s.total);
+    let Some(best) = response.results.first().and_then(total) else {
+        return Vec::new();
+    };
+    response
+        .results
+        .iter()
+        .take_while(|library| total(library).is_some_and(|t| best - t <= CLOSE_MATCH_MARGIN))
+        .take(MAX_CLOSE_MATCHES)
+        .collect()
+}
+
+/// Drops libraries that fail the `includeStates` (finalized only by default),
+/// `minTrustScore`, `minSnippets` and `verifiedOnly` filters.
+pub(crate) fn filter_libraries(
+    response: &mut ResolveLibraryIdResponse,
+    args: &ResolveLibraryIdArguments,
+) {
+    let default_states = [DocumentState::Finalized];
+    let states = args.include_states.as_deref().unwrap_or(&default_states);
+
+    let before = response.results.len();
+    response.results.retain(|library| {
+        states.contains(&library.state)
+            && args
+                .min_trust_score
+                .is_none_or(|min| library.trust_score.unwrap_or_default() >= min)
+            && args
+                .min_snippets
+                .is_none_or(|min| library.total_snippets >= min as f64)
+            && (args.verified_only != Some(true) || library.verified == Some(true))
+    });
+
+    let removed = (before - response.results.len()) as u32;
+    response.filtered_out = (removed > 0).then_some(removed);
+}
+
+fn versions(library: &Library) -> String {
+    if library.versions.is_empty() {
+        "-".to_string()
+    } else {
+        library.versions.join(", ")
+    }
+}
+
+fn trust(library: &Library) -> String {
+    library
+        .trust_score
+        .map_or_else(|| "-".to_string(), |t| t.to_string())
+}
+
+fn escape_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+/// Renders the text content of a `resolve_library_id` result in the requested
+/// format.
+fn format_libraries(response: &ResolveLibraryIdResponse, format: ResolveFormat) -> String {
+    let mut out = String::new();
+
+    match format {
+        ResolveFormat::Json => return serde_json::to_string(response).unwrap_or_default(),
+        ResolveFormat::Compact => {
+            if let Some(best) = &response.best_match {
+                let _ = writeln!(out, "Best match: {best}");
+            }
+            for library in &response.results {
+                let _ = writeln!(
+                    out,
+                    "{} | {} | versions: {} | snippets: {} | trust: {}",
+                    library.id,
+                    library.title,
+                    versions(library),
+                    library.total_snippets,
+                    trust(library),
+                );
+            }
+        }
+        ResolveFormat::Markdown => {
+            if let Some(best) = &response.best_match {
+                let _ = writeln!(out, "**Best match:** `{best}`\n");
+            }
+            out.push_str("| ID | Title | Versions | Snippets | Trust |\n");
+            out.push_str("|----|-------|----------|----------|-------|\n");
+            for library in &response.results {
+                let _ = writeln!(
+                    out,
+                    "| `{}` | {} | {} | {} | {} |",
+                    library.id,
+                    escape_cell(&library.title),
+                    escape_cell(&versions(library)),
+                    library.total_snippets,
+                    trust(library),
+                );
+            }
+        }
+    }
+
+    if response.results.is_empty() {
+        out.push_str("No matching libraries found.\n");
+    }
+    if let Some(error) = &response.error {
+        let _ = writeln!(out, "Error: {error}");
+    }
+    out.trim_end().to_string()
+}
+
+// ---------------------------------------------------------------------------
+// Replicated helpers from aliases.rs (logging replaced with a no-op)
+// ---------------------------------------------------------------------------
+
+fn warn(_message: String) {}
+
+/// Alias keys are matched ignoring case and surrounding whitespace.
+fn normalize_alias(alias: &str) -> String {
+    alias.trim().to_lowercase()
+}
+
+/// Parses a JSON object mapping aliases to library IDs. Entries whose value is
+/// not a `/org/project` ID are skipped.
+fn parse_aliases(source: &str, json: &str) -> HashMap<String, String> {
+    let map: HashMap<String, String> = match serde_json::from_str(json) {
+        Ok(map) => map,
+        Err(e) => {
+            warn(format!("Ignoring library aliases from {source}: {e}"));
+            return HashMap::new();
+        }
+    };
+
+    map.into_iter()
+        .filter_map(|(alias, id)| {
+            let id = id.trim();
+            if id.starts_with('/') && id.len() > 1 {
+                Some((normalize_alias(&alias), id.to_string()))
+            } else {
+                warn(format!(
+                    "Ignoring library alias '{alias}' from {source}: '{id}' is not a library ID"
+                ));
+                None
+            }
+        })
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Helpers to build test data
+// ---------------------------------------------------------------------------
+
+fn library(id: &str, title: &str) -> Library {
+    Library {
+        id: id.to_string(),
+        title: title.to_string(),
+        total_snippets: 100.0,
+        state: DocumentState::Finalized,
+        ..Default::default()
+    }
+}
+
+// ===========================================================================
+// Tests
+// ===========================================================================
+
+// --- Best-match selection ---
+
+#[test]
+fn test_score_breakdown_sums_to_total() {
+    let lib = Library {
+        trust_score: Some(8.0),
+        benchmark_score: Some(50.0),
+        verified: Some(true),
+        ..library("/vercel/next.js", "Next.js")
+    };
+    let s = score(&lib, "nextjs");
+
+    assert_eq!(s.name_match, EXACT_NAME_POINTS);
+    assert_eq!(s.verified, VERIFIED_POINTS);
+    assert_eq!(s.trust, 6.4);
+    assert_eq!(s.benchmark, 2.0);
+    assert_eq!(s.state, FINALIZED_POINTS);
+    let sum = s.name_match + s.verified + s.trust + s.benchmark + s.snippets + s.state;
+    assert!((s.total - sum).abs() < 0.02, "{s:?}");
+}
+
+#[test]
+fn test_score_perfect_library_is_100() {
+    let lib = Library {
+        trust_score: Some(10.0),
+        benchmark_score: Some(100.0),
+        verified: Some(true),
+        total_snippets: 50_000.0,
+        ..library("/facebook/react", "React")
+    };
+    assert_eq!(score(&lib, "React").total, 100.0);
+}
+
+#[test]
+fn test_exact_name_match_outranks_popular_partial_match() {
+    let mut response = ResolveLibraryIdResponse {
+        results: vec![
+            Library {
+                trust_score: Some(10.0),
+                benchmark_score: Some(90.0),
+                total_snippets: 20_000.0,
+                ..library("/websites/react-router", "React Router")
+            },
+            Library {
+                trust_score: Some(7.0),
+                ..library("/facebook/react", "React")
+            },
+        ],
+        ..Default::default()
+    };
+    select_best_match(&mut response, "react");
+
+    assert_eq!(response.best_match.as_deref(), Some("/facebook/react"));
+    assert_eq!(response.results[0].id, "/facebook/react");
+    assert!(response.results.iter().all(|l| l.match_score.is_some()));
+}
+
+#[test]
+fn test_exact_name_match_outranks_verified_trusted_partial_match() {
+    let mut response = ResolveLibraryIdResponse {
+        results: vec![
+            Library {
+                trust_score: Some(10.0),
+                benchmark_score: Some(100.0),
+                verified: Some(true),
+                total_snippets: 50_000.0,
+                ..library("/websites/react_dev", "react.dev")
+            },
+            Library {
+                total_snippets: 0.0,
+                state: DocumentState::Initial,
+                ..library("/someone/react", "React")
+            },
+        ],
+        ..Default::default()
+    };
+    select_best_match(&mut response, "react");
+
+    assert_eq!(response.best_match.as_deref(), Some("/someone/react"));
+    let totals: Vec<f64> = response
+        .results
+        .iter()
+        .map(|l| l.match_score.as_ref().unwrap().total)
+        .collect();
+    assert_eq!(
+        totals,
+        [
+            EXACT_NAME_POINTS,
+            100.0 - EXACT_NAME_POINTS + PARTIAL_NAME_POINTS
+        ]
+    );
+}
+
+#[test]
+fn test_each_criterion_outweighs_all_later_ones() {
+    let later = [
+        VERIFIED_POINTS,
+        TRUST_POINTS,
+        BENCHMARK_POINTS,
+        SNIPPETS_POINTS,
+        FINALIZED_POINTS,
+    ];
+    assert!(EXACT_NAME_POINTS - PARTIAL_NAME_POINTS > later.iter().sum::<f64>());
+    assert!(PARTIAL_NAME_POINTS > later.iter().sum::<f64>());
+    for (i, points) in later.iter().enumerate() {
+        assert!(*points > later[i + 1..].iter().sum::<f64>(), "{points}");
+    }
+}
+
+#[test]
+fn test_trust_outranks_benchmark_with_continuous_values() {
+    // A has the larger total (8 vs 7.2 points), but B's trust is higher
+    let mut response = ResolveLibraryIdResponse {
+        results: vec![
+            Library {
+                trust_score: Some(5.0),
+                benchmark_score: Some(100.0),
+                ..library("/someone/axum", "axum")
+            },
+            Library {
+                trust_score: Some(9.0),
+                benchmark_score: Some(0.0),
+                ..library("/tokio-rs/axum", "axum")
+            },
+        ],
+        ..Default::default()
+    };
+    select_best_match(&mut response, "axum");
+
+    assert_eq!(response.best_match.as_deref(), Some("/tokio-rs/axum"));
+    let total = |i: usize| response.results[i].match_score.as_ref().unwrap().total;
+    assert!(total(0) < total(1));
+}
+
+#[test]
+fn test_verified_breaks_name_ties() {
+    let mut response = ResolveLibraryIdResponse {
+        results: vec![
+            library("/someone/axum", "axum"),
+            Library {
+                verified: Some(true),
+                ..library("/tokio-rs/axum", "axum")
+            },
+        ],
+        ..Default::default()
+    };
+    select_best_match(&mut response, "axum");
+
+    assert_eq!(response.best_match.as_deref(), Some("/tokio-rs/axum"));
+}
+
+#[test]
+fn test_exact_id_match() {
+    let lib = library("/vercel/next.js", "Next");
+    assert_eq!(score(&lib, "/vercel/next.js").name_match, EXACT_NAME_POINTS);
+    assert_eq!(score(&lib, "vercel").name_match, 0.0);
+}
+
+#[test]
+fn test_best_match_empty_results() {
+    let mut response = ResolveLibraryIdResponse::default();
+    select_best_match(&mut response, "anything");
+    assert!(response.best_match.is_none());
+}
+
+// --- Disambiguation ---
+
+#[test]
+fn test_close_matches_within_margin() {
+    let mut response = ResolveLibraryIdResponse {
+        results: vec![
+            Library {
+                trust_score: Some(6.0),
+                ..library("/someone/axum", "axum")
+            },
+            Library {
+                trust_score: Some(9.0),
+                ..library("/tokio-rs/axum", "axum")
+            },
+            library("/websites/axum-extra", "axum-extra"),
+        ],
+        ..Default::default()
+    };
+    select_best_match(&mut response, "axum");
+
+    let ids: Vec<&str> = close_matches(&response)
+        .iter()
+        .map(|l| l.id.as_str())
+        .collect();
+    assert_eq!(ids, ["/tokio-rs/axum", "/someone/axum"]);
+}
+
+#[test]
+fn test_close_matches_clear_winner_and_cap() {
+    let mut response = ResolveLibraryIdResponse {
+        results: vec![
+            library("/facebook/react", "React"),
+            library("/websites/react-router", "React Router"),
+        ],
+        ..Default::default()
+    };
+    select_best_match(&mut response, "react");
+    assert_eq!(close_matches(&response).len(), 1);
+
+    let mut response = ResolveLibraryIdResponse {
+        results: (0..8)
+            .map(|i| library(&format!("/org{i}/zod"), "zod"))
+            .collect(),
+        ..Default::default()
+    };
+    select_best_match(&mut response, "zod");
+    assert_eq!(close_matches(&response).len(), MAX_CLOSE_MATCHES);
+
+    assert!(close_matches(&ResolveLibraryIdResponse::default()).is_empty());
+}
+
+// --- Filtering ---
+
+fn mixed_response() -> ResolveLibraryIdResponse {
+    ResolveLibraryIdResponse {
+        results: vec![
+            Library {
+                trust_score: Some(9.0),
+                verified: Some(true),
+                total_snippets: 2000.0,
+                ..library("/org/good", "Good")
+            },
+            Library {
+                trust_score: Some(3.0),
+                total_snippets: 5.0,
+                ..library("/org/weak", "Weak")
+            },
+            Library {
+                state: DocumentState::Error,
+                ..library("/org/broken", "Broken")
+            },
+            Library {
+                state: DocumentState::Initial,
+                ..library("/org/pending", "Pending")
+            },
+        ],
+        ..Default::default()
+    }
+}
+
+fn ids(response: &ResolveLibraryIdResponse) -> Vec<&str> {
+    response.results.iter().map(|l| l.id.as_str()).collect()
+}
+
+#[test]
+fn test_filter_drops_unfinalized_by_default() {
+    let mut response = mixed_response();
+    filter_libraries(&mut response, &ResolveLibraryIdArguments::default());
+
+    assert_eq!(ids(&response), vec!["/org/good", "/org/weak"]);
+    assert_eq!(response.filtered_out, Some(2));
+}
+
+#[test]
+fn test_filter_include_states_overrides_default() {
+    let mut response = mixed_response();
+    let args = ResolveLibraryIdArguments {
+        include_states: Some(vec![DocumentState::Finalized, DocumentState::Initial]),
+        ..Default::default()
+    };
+    filter_libraries(&mut response, &args);
+
+    assert_eq!(
+        ids(&response),
+        vec!["/org/good", "/org/weak", "/org/pending"]
+    );
+}
+
+#[test]
+fn test_filter_quality_thresholds() {
+    let mut response = mixed_response();
+    let args = ResolveLibraryIdArguments {
+        min_trust_score: Some(5.0),
+        ..Default::default()
+    };
+    filter_libraries(&mut response, &args);
+    assert_eq!(ids(&response), vec!["/org/good"]);
+
+    let mut response = mixed_response();
+    let args = ResolveLibraryIdArguments {
+        min_snippets: Some(10),
+        ..Default::default()
+    };
+    filter_libraries(&mut response, &args);
+    assert_eq!(ids(&response), vec!["/org/good"]);
+
+    let mut response = mixed_response();
+    let args = ResolveLibraryIdArguments {
+        verified_only: Some(true),
+        ..Default::default()
+    };
+    filter_libraries(&mut response, &args);
+    assert_eq!(ids(&response), vec!["/org/good"]);
+    assert_eq!(response.filtered_out, Some(3));
+}
+
+#[test]
+fn test_filter_nothing_removed_reports_nothing() {
+    let mut response = ResolveLibraryIdResponse {
+        results: vec![library("/org/good", "Good")],
+        ..Default::default()
+    };
+    filter_libraries(&mut response, &ResolveLibraryIdArguments::default());
+    assert!(response.filtered_out.is_none());
+}
+
+// --- Output formats ---
+
+fn formatted_response() -> ResolveLibraryIdResponse {
+    ResolveLibraryIdResponse {
+        results: vec![
+            Library {
+                trust_score: Some(10.0),
+                total_snippets: 3200.0,
+                versions: vec!["v14.3.0".to_string(), "v15.1.8".to_string()],
+                ..library("/vercel/next.js", "Next.js")
+            },
+            library("/org/pipe", "A | B"),
+        ],
+        best_match: Some("/vercel/next.js".to_string()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_format_json_is_full_response() {
+    let response = formatted_response();
+    let text = format_libraries(&response, ResolveFormat::Json);
+    let parsed: ResolveLibraryIdResponse = serde_json::from_str(&text).unwrap();
+    assert_eq!(parsed.results.len(), 2);
+    assert_eq!(parsed.best_match.as_deref(), Some("/vercel/next.js"));
+}
+
+#[test]
+fn test_format_compact_one_line_per_library() {
+    let text = format_libraries(&formatted_response(), ResolveFormat::Compact);
+    let lines: Vec<&str> = text.lines().collect();
+
+    assert_eq!(
+        lines,
+        vec![
+            "Best match: /vercel/next.js",
+            "/vercel/next.js | Next.js | versions: v14.3.0, v15.1.8 | snippets: 3200 | trust: 10",
+            "/org/pipe | A | B | versions: - | snippets: 100 | trust: -",
+        ]
+    );
+}
+
+#[test]
+fn test_format_markdown_table_escapes_pipes() {
+    let text = format_libraries(&formatted_response(), ResolveFormat::Markdown);
+
+    assert!(text.starts_with("**Best match:** `/vercel/next.js`"));
+    assert!(text.contains("| ID | Title | Versions | Snippets | Trust |"));
+    assert!(text.contains("| `/vercel/next.js` | Next.js | v14.3.0, v15.1.8 | 3200 | 10 |"));
+    assert!(text.contains("| `/org/pipe` | A \\| B | - | 100 | - |"));
+}
+
+#[test]
+fn test_format_empty_results() {
+    let response = ResolveLibraryIdResponse::default();
+    assert_eq!(
+        format_libraries(&response, ResolveFormat::Compact),
+        "No matching libraries found."
+    );
+    assert!(
+        format_libraries(&response, ResolveFormat::Markdown)
+            .ends_with("No matching libraries found.")
+    );
+}
+
+// --- Aliases ---
+
+#[test]
+fn test_aliases_parse_and_normalize_keys() {
+    let aliases = parse_aliases(
+        "test",
+        r#"{"npm:React": "/facebook/react", " crates:serde ": "/serde-rs/serde", "nextjs": " /vercel/next.js "}"#,
+    );
+
+    assert_eq!(aliases.len(), 3);
+    assert_eq!(
+        aliases
+            .get(&normalize_alias("NPM:react"))
+            .map(String::as_str),
+        Some("/facebook/react")
+    );
+    assert_eq!(
+        aliases.get("crates:serde").map(String::as_str),
+        Some("/serde-rs/serde")
+    );
+    assert_eq!(
+        aliases.get("nextjs").map(String::as_str),
+        Some("/vercel/next.js")
+    );
+}
+
+#[test]
+fn test_aliases_skip_invalid_ids() {
+    let aliases = parse_aliases(
+        "test",
+        r#"{"pypi:requests": "/psf/requests", "bad": "react", "empty": "/"}"#,
+    );
+    assert_eq!(aliases.len(), 1);
+    assert!(aliases.contains_key("pypi:requests"));
+}
+
+#[test]
+fn test_aliases_invalid_json_is_empty() {
+    assert!(parse_aliases("test", "not json").is_empty());
+    assert!(parse_aliases("test", r#"["/facebook/react"]"#).is_empty());
+}
@@ -0,0 +1,171 @@
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+
+// ---------------------------------------------------------------------------
+// Replicated logging helpers from log.rs
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq)]
+enum LoggingLevel {
+    Debug,
+    Info,
+    Notice,
+    Warning,
+    Error,
+    Critical,
+    Alert,
+    Emergency,
+}
+
+/// Headers whose values are never logged.
+const SECRET_HEADERS: &[&str] = &["authorization", "cookie", "x-api-key"];
+/// Prefix of Context7 API keys.
+const CONTEXT7_KEY_PREFIX: &str = "ctx7sk";
+const REDACTED: &str = "[REDACTED]";
+
+fn severity(level: &LoggingLevel) -> u8 {
+    match level {
+        LoggingLevel::Debug => 0,
+        LoggingLevel::Info => 1,
+        LoggingLevel::Notice => 2,
+        LoggingLevel::Warning => 3,
+        LoggingLevel::Error => 4,
+        LoggingLevel::Critical => 5,
+        LoggingLevel::Alert => 6,
+        LoggingLevel::Emergency => 7,
+    }
+}
+
+fn parse_level(value: Option<&str>) -> LoggingLevel {
+    match value.map(|v| v.to_lowercase()).as_deref() {
+        Some("debug" | "trace") => LoggingLevel::Debug,
+        Some("notice") => LoggingLevel::Notice,
+        Some("warning" | "warn") => LoggingLevel::Warning,
+        Some("error") => LoggingLevel::Error,
+        Some("critical") => LoggingLevel::Critical,
+        Some("alert") => LoggingLevel::Alert,
+        Some("emergency") => LoggingLevel::Emergency,
+        _ => LoggingLevel::Info,
+    }
+}
+
+fn enabled(level: &LoggingLevel, min: &LoggingLevel) -> bool {
+    severity(level) >= severity(min)
+}
+
+fn scrub(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut after_bearer = false;
+    for (i, word) in text.split(' ').enumerate() {
+        if i > 0 {
+            out.push(' ');
+        }
+        let token = word.trim_matches(|c: char| !c.is_ascii_alphanumeric() && c != '_');
+        if (after_bearer && !token.is_empty()) || token.starts_with(CONTEXT7_KEY_PREFIX) {
+            out.push_str(&word.replacen(token, REDACTED, 1));
+        } else {
+            out.push_str(word);
+        }
+        after_bearer = word.eq_ignore_ascii_case("bearer");
+    }
+    out
+}
+
+fn scrub_value(value: Value) -> Value {
+    match value {
+        Value::String(text) => Value::String(scrub(&text)),
+        Value::Array(items) => Value::Array(items.into_iter().map(scrub_value).collect()),
+        Value::Object(map) => {
+            Value::Object(map.into_iter().map(|(k, v)| (k, scrub_value(v))).collect())
+        }
+        other => other,
+    }
+}
+
+fn headers(headers: &BTreeMap<String, String>) -> Value {
+    Value::Object(
+        headers
+            .iter()
+            .map(|(name, value)| {
+                let value = if SECRET_HEADERS.contains(&name.to_lowercase().as_str()) {
+                    REDACTED.to_string()
+                } else {
+                    value.clone()
+                };
+                (name.clone(), Value::String(value))
+            })
+            .collect(),
+    )
+}
+
+// ===========================================================================
+// Tests
+// ===========================================================================
+
+#[test]
+fn test_level_parsing_and_filtering() {
+    assert_eq!(parse_level(None), LoggingLevel::Info);
+    assert_eq!(parse_level(Some("DEBUG")), LoggingLevel::Debug);
+    assert_eq!(parse_level(Some("warn")), LoggingLevel::Warning);
+    assert_eq!(parse_level(Some("verbose")), LoggingLevel::Info);
+
+    let min = parse_level(Some("warning"));
+    assert!(!enabled(&LoggingLevel::Info, &min));
+    assert!(enabled(&LoggingLevel::Warning, &min));
+    assert!(enabled(&LoggingLevel::Emergency, &min));
+    assert!(!enabled(&LoggingLevel::Debug, &LoggingLevel::Info));
+}
+
+#[test]
+fn test_scrub_bearer_tokens_and_context7_keys() {
+    assert_eq!(
+        scrub("Authorization: Bearer abc.def-123"),
+        "Authorization: Bearer [REDACTED]"
+    );
+    assert_eq!(
+        scrub("key 'ctx7sk-1234abcd' was rejected"),
+        "key '[REDACTED]' was rejected"
+    );
+    assert_eq!(
+        scrub("Cache hit for /vercel/next.js"),
+        "Cache hit for /vercel/next.js"
+    );
+}
+
+#[test]
+fn test_headers_mask_credentials() {
+    let map = BTreeMap::from([
+        (
+            "Authorization".to_string(),
+            "Bearer ctx7sk-secret".to_string(),
+        ),
+        (
+            "X-Context7-Source".to_string(),
+            "hyper-mcp/context7-plugin".to_string(),
+        ),
+    ]);
+    assert_eq!(
+        headers(&map),
+        json!({
+            "Authorization": "[REDACTED]",
+            "X-Context7-Source": "hyper-mcp/context7-plugin"
+        })
+    );
+}
+
+#[test]
+fn test_scrub_nested_fields() {
+    let data = json!({
+        "message": "JSON API request failed",
+        "url": "https://context7.com/api/v2/context?libraryId=%2Fvercel%2Fnext.js",
+        "details": ["Bearer ctx7sk-abc", 429],
+    });
+    assert_eq!(
+        scrub_value(data),
+        json!({
+            "message": "JSON API request failed",
+            "url": "https://context7.com/api/v2/context?libraryId=%2Fvercel%2Fnext.js",
+            "details": ["Bearer [REDACTED]", 429],
+        })
+    );
+}
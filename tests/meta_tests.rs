@@ -0,0 +1,200 @@
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+
+// ---------------------------------------------------------------------------
+// Replicated result metadata helpers from meta.rs
+// ---------------------------------------------------------------------------
+
+const PLUGIN_VERSION: &str = "0.4.0";
+
+#[derive(Debug, Default, Clone)]
+struct CallToolResult {
+    meta: Option<Map<String, Value>>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct UpstreamStats {
+    attempts: u32,
+    status: Option<u16>,
+    latency_ms: i64,
+}
+
+impl UpstreamStats {
+    fn record_attempt(&mut self, status: Option<u16>, latency_ms: i64) {
+        self.attempts += 1;
+        self.status = status.or(self.status);
+        self.latency_ms += latency_ms;
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ResultMeta {
+    #[serde(rename = "cacheHit")]
+    cache_hit: bool,
+    #[serde(rename = "fetchedAt")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fetched_at: Option<String>,
+    #[serde(rename = "ageSecs")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    age_secs: Option<i64>,
+    #[serde(rename = "upstreamStatus")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    upstream_status: Option<u16>,
+    attempts: u32,
+    #[serde(rename = "latencyMs")]
+    latency_ms: i64,
+    #[serde(rename = "libraryId")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    library_id: Option<String>,
+    #[serde(rename = "pluginVersion")]
+    plugin_version: String,
+}
+
+fn insert(result: &mut CallToolResult, meta: ResultMeta) {
+    if let Ok(Value::Object(fields)) = serde_json::to_value(meta) {
+        result.meta.get_or_insert_with(Map::new).extend(fields);
+    }
+}
+
+fn stamp(
+    result: &mut CallToolResult,
+    stats: UpstreamStats,
+    library_id: Option<&str>,
+    now: DateTime<Utc>,
+) {
+    insert(
+        result,
+        ResultMeta {
+            cache_hit: false,
+            fetched_at: Some(now.to_rfc3339_opts(SecondsFormat::Secs, true)),
+            age_secs: Some(0),
+            upstream_status: stats.status,
+            attempts: stats.attempts,
+            latency_ms: stats.latency_ms,
+            library_id: library_id.map(str::to_string),
+            plugin_version: PLUGIN_VERSION.to_string(),
+        },
+    );
+}
+
+fn mark_cached(result: &mut CallToolResult, library_id: Option<&str>, now: DateTime<Utc>) {
+    let stored = result.meta.clone().unwrap_or_default();
+    let fetched_at = stored
+        .get("fetchedAt")
+        .and_then(Value::as_str)
+        .and_then(|t| DateTime::parse_from_rfc3339(t).ok());
+    insert(
+        result,
+        ResultMeta {
+            cache_hit: true,
+            fetched_at: fetched_at.map(|t| t.to_rfc3339_opts(SecondsFormat::Secs, true)),
+            age_secs: fetched_at.map(|t| (now - t.with_timezone(&Utc)).num_seconds()),
+            upstream_status: stored
+                .get("upstreamStatus")
+                .and_then(Value::as_u64)
+                .map(|s| s as u16),
+            attempts: stored
+                .get("attempts")
+                .and_then(Value::as_u64)
+                .unwrap_or_default() as u32,
+            latency_ms: stored
+                .get("latencyMs")
+                .and_then(Value::as_i64)
+                .unwrap_or_default(),
+            library_id: library_id.map(str::to_string),
+            plugin_version: PLUGIN_VERSION.to_string(),
+        },
+    );
+}
+
+// ---------------------------------------------------------------------------
+// Helpers to build test data
+// ---------------------------------------------------------------------------
+
+fn fetched_at() -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339("2026-01-15T10:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc)
+}
+
+// ===========================================================================
+// Tests
+// ===========================================================================
+
+#[test]
+fn test_stamp_records_upstream_attempts() {
+    let mut stats = UpstreamStats::default();
+    stats.record_attempt(Some(503), 120);
+    stats.record_attempt(None, 30);
+    stats.record_attempt(Some(200), 80);
+
+    let mut result = CallToolResult::default();
+    stamp(&mut result, stats, Some("/vercel/next.js"), fetched_at());
+    assert_eq!(
+        Value::Object(result.meta.unwrap()),
+        json!({
+            "cacheHit": false,
+            "fetchedAt": "2026-01-15T10:00:00Z",
+            "ageSecs": 0,
+            "upstreamStatus": 200,
+            "attempts": 3,
+            "latencyMs": 230,
+            "libraryId": "/vercel/next.js",
+            "pluginVersion": PLUGIN_VERSION,
+        })
+    );
+}
+
+#[test]
+fn test_network_errors_keep_last_status() {
+    let mut stats = UpstreamStats::default();
+    stats.record_attempt(None, 10);
+    assert_eq!(stats.status, None);
+    stats.record_attempt(Some(429), 10);
+    stats.record_attempt(None, 10);
+    assert_eq!(stats.status, Some(429));
+    assert_eq!(stats.attempts, 3);
+}
+
+#[test]
+fn test_cached_result_keeps_original_fetch_and_reports_age() {
+    let mut stats = UpstreamStats::default();
+    stats.record_attempt(Some(200), 150);
+    let mut result = CallToolResult::default();
+    stamp(&mut result, stats, Some("/vercel/next.js"), fetched_at());
+
+    mark_cached(
+        &mut result,
+        Some("/vercel/next.js"),
+        fetched_at() + Duration::seconds(90),
+    );
+    let meta = result.meta.unwrap();
+    assert_eq!(meta["cacheHit"], json!(true));
+    assert_eq!(meta["fetchedAt"], json!("2026-01-15T10:00:00Z"));
+    assert_eq!(meta["ageSecs"], json!(90));
+    assert_eq!(meta["upstreamStatus"], json!(200));
+    assert_eq!(meta["attempts"], json!(1));
+    assert_eq!(meta["latencyMs"], json!(150));
+}
+
+#[test]
+fn test_mark_cached_preserves_near_match_and_legacy_entries() {
+    // Entries cached before metadata existed have no fetch time
+    let mut result = CallToolResult {
+        meta: Some(
+            json!({ "nearMatch": { "cachedQuery": "app router", "similarity": 0.8 } })
+                .as_object()
+                .unwrap()
+                .clone(),
+        ),
+    };
+    mark_cached(&mut result, None, fetched_at());
+    let meta = result.meta.unwrap();
+    assert_eq!(meta["cacheHit"], json!(true));
+    assert_eq!(meta["nearMatch"]["cachedQuery"], json!("app router"));
+    assert!(!meta.contains_key("fetchedAt"));
+    assert!(!meta.contains_key("ageSecs"));
+    assert!(!meta.contains_key("libraryId"));
+    assert_eq!(meta["attempts"], json!(0));
+}
@@ -0,0 +1,234 @@
+// ---------------------------------------------------------------------------
+// Replicated policy helpers from policy.rs so they can be tested without the
+// PDK runtime.
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Default)]
+struct Policy {
+    pub allow: Vec<String>,
+    pub deny: Vec<String>,
+    pub min_trust_score: Option<f64>,
+}
+
+/// Matches `text` against a glob where `*` matches any run of characters and
+/// `?` matches exactly one.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Returns the `/org/project` part of a library ID, dropping any version.
+fn base_id(library_id: &str) -> String {
+    let trimmed = library_id.trim().trim_matches('/').to_lowercase();
+    let base: Vec<&str> = trimmed.splitn(3, '/').take(2).collect();
+    format!("/{}", base.join("/"))
+}
+
+impl Policy {
+    /// Checks a library ID against the deny and allow patterns, returning the
+    /// reason it is blocked. Deny patterns take precedence.
+    fn check_id(&self, library_id: &str) -> Result<(), String> {
+        let id = base_id(library_id);
+        if let Some(pattern) = self.deny.iter().find(|p| glob_match(p, &id)) {
+            return Err(format!(
+                "Library '{library_id}' is blocked by policy: it matches the deny pattern '{pattern}'"
+            ));
+        }
+        if !self.allow.is_empty() && !self.allow.iter().any(|p| glob_match(p, &id)) {
+            return Err(format!(
+                "Library '{library_id}' is blocked by policy: it does not match any allowed pattern"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks a library's trust score against `MIN_TRUST_SCORE`. Libraries
+    /// without a trust score are blocked when a floor is set.
+    fn check_trust(&self, library_id: &str, trust_score: Option<f64>) -> Result<(), String> {
+        let Some(min) = self.min_trust_score else {
+            return Ok(());
+        };
+        match trust_score {
+            Some(score) if score >= min => Ok(()),
+            Some(score) => Err(format!(
+                "Library '{library_id}' is blocked by policy: its trust score {score} is below the minimum {min}"
+            )),
+            None => Err(format!(
+                "Library '{library_id}' is blocked by policy: its trust score is unknown and the minimum is {min}"
+            )),
+        }
+    }
+}
+
+/// Minimal `resolve_library_id` response types: results sorted best first.
+#[derive(Debug, Default, Clone)]
+struct Library {
+    id: String,
+    trust_score: Option<f64>,
+}
+
+#[derive(Debug, Default)]
+struct ResolveLibraryIdResponse {
+    results: Vec<Library>,
+    best_match: Option<String>,
+    blocked_by_policy: Option<u32>,
+}
+
+impl Policy {
+    /// Removes libraries that fail the policy, returning how many were removed.
+    fn filter(&self, response: &mut ResolveLibraryIdResponse) -> u32 {
+        let before = response.results.len();
+        response.results.retain(|library| {
+            self.check_id(&library.id)
+                .and_then(|_| self.check_trust(&library.id, library.trust_score))
+                .is_ok()
+        });
+        (before - response.results.len()) as u32
+    }
+
+    /// Re-applies the policy to a cached response, which may predate a policy
+    /// change. Blocked libraries are added to `blockedByPolicy` and the best
+    /// match moves to the first remaining result. Returns how many were
+    /// removed.
+    fn refilter(&self, response: &mut ResolveLibraryIdResponse) -> u32 {
+        let blocked = self.filter(response);
+        if blocked > 0 {
+            response.blocked_by_policy =
+                Some(response.blocked_by_policy.unwrap_or_default() + blocked);
+            response.best_match = response.results.first().map(|l| l.id.clone());
+        }
+        blocked
+    }
+}
+
+fn patterns(list: &[&str]) -> Vec<String> {
+    list.iter().map(|p| p.to_lowercase()).collect()
+}
+
+// ===========================================================================
+// Tests
+// ===========================================================================
+
+#[test]
+fn test_glob_match() {
+    assert!(glob_match("/vercel/*", "/vercel/next.js"));
+    assert!(glob_match("*/react", "/facebook/react"));
+    assert!(glob_match("/org/pro?ect", "/org/project"));
+    assert!(glob_match("*", "/any/thing"));
+    assert!(glob_match("/exact/id", "/exact/id"));
+    assert!(!glob_match("/vercel/*", "/facebook/react"));
+    assert!(!glob_match("/exact/id", "/exact/id2"));
+    assert!(!glob_match("/org/pro?ect", "/org/proect"));
+}
+
+#[test]
+fn test_base_id_drops_version_and_normalizes() {
+    assert_eq!(base_id("/vercel/next.js/v15.1.8"), "/vercel/next.js");
+    assert_eq!(base_id("/Vercel/Next.js/"), "/vercel/next.js");
+    assert_eq!(base_id("facebook/react"), "/facebook/react");
+}
+
+#[test]
+fn test_deny_takes_precedence_over_allow() {
+    let policy = Policy {
+        allow: patterns(&["/vercel/*"]),
+        deny: patterns(&["/vercel/legacy-*"]),
+        ..Default::default()
+    };
+
+    assert!(policy.check_id("/vercel/next.js").is_ok());
+    assert!(policy.check_id("/vercel/next.js/v15.1.8").is_ok());
+    let err = policy.check_id("/vercel/legacy-app").unwrap_err();
+    assert!(err.contains("deny pattern '/vercel/legacy-*'"), "{err}");
+    let err = policy.check_id("/facebook/react").unwrap_err();
+    assert!(err.contains("does not match any allowed pattern"), "{err}");
+}
+
+#[test]
+fn test_empty_policy_allows_everything() {
+    let policy = Policy::default();
+    assert!(policy.check_id("/any/library").is_ok());
+    assert!(policy.check_trust("/any/library", None).is_ok());
+}
+
+#[test]
+fn test_patterns_are_case_insensitive() {
+    let policy = Policy {
+        deny: patterns(&["/MongoDB/*"]),
+        ..Default::default()
+    };
+    assert!(policy.check_id("/mongodb/docs").is_err());
+    assert!(policy.check_id("/MONGODB/docs").is_err());
+}
+
+#[test]
+fn test_trust_floor() {
+    let policy = Policy {
+        min_trust_score: Some(7.0),
+        ..Default::default()
+    };
+
+    assert!(policy.check_trust("/org/good", Some(7.0)).is_ok());
+    let err = policy.check_trust("/org/weak", Some(3.5)).unwrap_err();
+    assert!(
+        err.contains("trust score 3.5 is below the minimum 7"),
+        "{err}"
+    );
+    let err = policy.check_trust("/org/unknown", None).unwrap_err();
+    assert!(err.contains("unknown"), "{err}");
+}
+
+#[test]
+fn test_cached_results_are_refiltered() {
+    let library = |id: &str, trust_score| Library {
+        id: id.to_string(),
+        trust_score: Some(trust_score),
+    };
+    let mut response = ResolveLibraryIdResponse {
+        results: vec![
+            library("/vercel/legacy-next", 9.0),
+            library("/vercel/next.js", 10.0),
+            library("/someone/next-fork", 2.0),
+        ],
+        best_match: Some("/vercel/legacy-next".to_string()),
+        blocked_by_policy: Some(1),
+    };
+    let policy = Policy {
+        deny: patterns(&["/vercel/legacy-*"]),
+        min_trust_score: Some(7.0),
+        ..Default::default()
+    };
+
+    assert_eq!(policy.refilter(&mut response), 2);
+    assert_eq!(response.results.len(), 1);
+    assert_eq!(response.best_match.as_deref(), Some("/vercel/next.js"));
+    assert_eq!(response.blocked_by_policy, Some(3));
+
+    // Nothing left to block: the response is unchanged
+    assert_eq!(policy.refilter(&mut response), 0);
+    assert_eq!(response.blocked_by_policy, Some(3));
+}
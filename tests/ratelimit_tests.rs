@@ -0,0 +1,254 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+// ---------------------------------------------------------------------------
+// Replicated rate-limit bookkeeping from ratelimit.rs
+// ---------------------------------------------------------------------------
+
+/// Block applied after a 429 without a `Retry-After` header.
+const DEFAULT_BLOCK_SECS: i64 = 15;
+/// Window for the anonymous requests-per-minute cap.
+const WINDOW_SECS: i64 = 60;
+/// `X-RateLimit-Reset` values above this are Unix timestamps rather than
+/// seconds from now.
+const EPOCH_THRESHOLD: i64 = 1_000_000_000;
+
+/// Budget reported by the API and local request history, as Unix timestamps.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+struct RateLimitState {
+    #[serde(default)]
+    remaining: Option<u64>,
+    #[serde(rename = "resetAt")]
+    #[serde(default)]
+    reset_at: Option<i64>,
+    #[serde(rename = "blockedUntil")]
+    #[serde(default)]
+    blocked_until: Option<i64>,
+    /// Start times of recent anonymous requests.
+    #[serde(default)]
+    anonymous: Vec<i64>,
+}
+
+/// Parses `X-RateLimit-Reset`, which may be a Unix timestamp or a number of
+/// seconds from now.
+fn parse_reset(value: &str, now: i64) -> Option<i64> {
+    let value = value.trim().parse::<f64>().ok()? as i64;
+    Some(if value > EPOCH_THRESHOLD {
+        value
+    } else {
+        now + value
+    })
+}
+
+/// Updates the state from a response's rate-limit headers.
+fn update(
+    state: &mut RateLimitState,
+    now: i64,
+    status: u16,
+    remaining: Option<&str>,
+    reset: Option<&str>,
+    retry_after: Option<Duration>,
+) {
+    if let Some(remaining) = remaining.and_then(|v| v.trim().parse().ok()) {
+        state.remaining = Some(remaining);
+    }
+    if let Some(reset) = reset.and_then(|v| parse_reset(v, now)) {
+        state.reset_at = Some(reset);
+    }
+    if status == 429 {
+        let wait = retry_after.map_or(DEFAULT_BLOCK_SECS, |d| d.as_secs() as i64);
+        state.blocked_until = Some(now + wait.max(1));
+    } else if (200..300).contains(&status) {
+        state.blocked_until = None;
+    }
+}
+
+/// Returns the time the next request must wait until, if any, and records an
+/// anonymous request when it may proceed.
+fn next_allowed(
+    state: &mut RateLimitState,
+    now: i64,
+    anonymous: bool,
+    anonymous_per_minute: Option<u32>,
+) -> Option<i64> {
+    state.blocked_until = state.blocked_until.filter(|until| *until > now);
+    if state.reset_at.is_some_and(|reset| reset <= now) {
+        state.remaining = None;
+        state.reset_at = None;
+    }
+    state.anonymous.retain(|at| *at > now - WINDOW_SECS);
+
+    let mut until = state.blocked_until;
+    if state.remaining == Some(0) {
+        until = until.max(state.reset_at);
+    }
+    if anonymous
+        && let Some(cap) = anonymous_per_minute
+        && state.anonymous.len() >= cap as usize
+    {
+        let oldest = state.anonymous.iter().min().copied().unwrap_or(now);
+        until = until.max(Some(oldest + WINDOW_SECS));
+    }
+
+    if until.is_none() && anonymous && anonymous_per_minute.is_some() {
+        state.anonymous.push(now);
+    }
+    until
+}
+
+/// Returns the state of `credential`, creating it on first use.
+fn state_for<'a>(
+    states: &'a mut BTreeMap<String, RateLimitState>,
+    credential: &str,
+) -> &'a mut RateLimitState {
+    states.entry(credential.to_string()).or_default()
+}
+
+// ===========================================================================
+// Tests
+// ===========================================================================
+
+const NOW: i64 = 1_760_000_000;
+
+#[test]
+fn test_parse_reset_relative_and_absolute() {
+    assert_eq!(parse_reset("30", NOW), Some(NOW + 30));
+    assert_eq!(parse_reset(" 1760000600 ", NOW), Some(1_760_000_600));
+    assert_eq!(parse_reset("12.5", NOW), Some(NOW + 12));
+    assert_eq!(parse_reset("soon", NOW), None);
+}
+
+#[test]
+fn test_update_records_budget_headers() {
+    let mut state = RateLimitState::default();
+    update(&mut state, NOW, 200, Some("42"), Some("60"), None);
+    assert_eq!(state.remaining, Some(42));
+    assert_eq!(state.reset_at, Some(NOW + 60));
+    assert!(state.blocked_until.is_none());
+}
+
+#[test]
+fn test_429_blocks_until_retry_after() {
+    let mut state = RateLimitState::default();
+    update(
+        &mut state,
+        NOW,
+        429,
+        None,
+        None,
+        Some(Duration::from_secs(45)),
+    );
+    assert_eq!(state.blocked_until, Some(NOW + 45));
+    assert_eq!(
+        next_allowed(&mut state, NOW + 10, false, None),
+        Some(NOW + 45)
+    );
+    assert_eq!(next_allowed(&mut state, NOW + 45, false, None), None);
+
+    // Without Retry-After a default block applies
+    let mut state = RateLimitState::default();
+    update(&mut state, NOW, 429, None, None, None);
+    assert_eq!(state.blocked_until, Some(NOW + DEFAULT_BLOCK_SECS));
+}
+
+#[test]
+fn test_success_clears_block() {
+    let mut state = RateLimitState {
+        blocked_until: Some(NOW + 100),
+        ..Default::default()
+    };
+    update(&mut state, NOW, 200, None, None, None);
+    assert!(state.blocked_until.is_none());
+}
+
+#[test]
+fn test_exhausted_budget_waits_for_reset() {
+    let mut state = RateLimitState::default();
+    update(&mut state, NOW, 200, Some("0"), Some("120"), None);
+
+    assert_eq!(next_allowed(&mut state, NOW, false, None), Some(NOW + 120));
+    // Once the reset passes the stale budget is dropped
+    assert_eq!(next_allowed(&mut state, NOW + 120, false, None), None);
+    assert!(state.remaining.is_none());
+}
+
+#[test]
+fn test_anonymous_cap() {
+    let mut state = RateLimitState::default();
+    for i in 0..3 {
+        assert_eq!(next_allowed(&mut state, NOW + i, true, Some(3)), None);
+    }
+    assert_eq!(
+        next_allowed(&mut state, NOW + 5, true, Some(3)),
+        Some(NOW + WINDOW_SECS)
+    );
+    // Authenticated requests are not capped
+    assert_eq!(next_allowed(&mut state, NOW + 5, false, Some(3)), None);
+    // The window slides
+    assert_eq!(
+        next_allowed(&mut state, NOW + WINDOW_SECS, true, Some(3)),
+        None
+    );
+}
+
+#[test]
+fn test_state_round_trips() {
+    let state = RateLimitState {
+        remaining: Some(5),
+        reset_at: Some(NOW),
+        blocked_until: None,
+        anonymous: vec![NOW - 1],
+    };
+    let json = serde_json::to_string(&state).unwrap();
+    assert!(json.contains("\"resetAt\""));
+    assert_eq!(
+        serde_json::from_str::<RateLimitState>(&json).unwrap(),
+        state
+    );
+    assert_eq!(
+        serde_json::from_str::<RateLimitState>("{}").unwrap(),
+        RateLimitState::default()
+    );
+}
+
+#[test]
+fn test_holds_are_tracked_per_credential() {
+    let mut states = BTreeMap::new();
+    update(
+        state_for(&mut states, "key-1"),
+        NOW,
+        429,
+        None,
+        None,
+        Some(Duration::from_secs(30)),
+    );
+    update(
+        state_for(&mut states, "key-2"),
+        NOW,
+        200,
+        Some("0"),
+        Some("60"),
+        None,
+    );
+
+    assert_eq!(
+        next_allowed(state_for(&mut states, "key-1"), NOW, false, None),
+        Some(NOW + 30)
+    );
+    assert_eq!(
+        next_allowed(state_for(&mut states, "key-2"), NOW, false, None),
+        Some(NOW + 60)
+    );
+    // A key without recorded limits is not held by the others
+    assert_eq!(
+        next_allowed(state_for(&mut states, "key-3"), NOW, false, None),
+        None
+    );
+
+    let json = serde_json::to_string(&states).unwrap();
+    assert_eq!(
+        serde_json::from_str::<BTreeMap<String, RateLimitState>>(&json).unwrap(),
+        states
+    );
+}
@@ -0,0 +1,333 @@
+use std::fmt::Write;
+
+// ---------------------------------------------------------------------------
+// Duplicated types from types.rs that the renderer operates on.
+// ---------------------------------------------------------------------------
+
+#[derive(Default, Debug, Clone)]
+struct CodeListEntry {
+    language: String,
+    code: String,
+}
+
+#[derive(Default, Debug, Clone)]
+struct CodeSnippet {
+    code_title: String,
+    code_description: String,
+    code_id: String,
+    page_title: String,
+    code_list: Vec<CodeListEntry>,
+}
+
+#[derive(Default, Debug, Clone)]
+struct InfoSnippet {
+    page_id: Option<String>,
+    breadcrumb: Option<String>,
+    content: String,
+}
+
+#[derive(Default, Debug, Clone)]
+struct Rules {
+    global: Vec<String>,
+    library_own: Vec<String>,
+    library_team: Vec<String>,
+}
+
+#[derive(Default, Debug, Clone)]
+struct QueryDocsResponse {
+    code_snippets: Vec<CodeSnippet>,
+    info_snippets: Vec<InfoSnippet>,
+    rules: Option<Rules>,
+}
+
+// ---------------------------------------------------------------------------
+// Replicated renderer from render.rs
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MarkdownLayout {
+    Sections,
+    Compact,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct RenderOptions {
+    layout: MarkdownLayout,
+    include_sources: bool,
+}
+
+fn parse_layout(value: Option<&str>) -> MarkdownLayout {
+    match value.map(|v| v.to_lowercase()).as_deref() {
+        Some("compact") => MarkdownLayout::Compact,
+        _ => MarkdownLayout::Sections,
+    }
+}
+
+fn fence_for(code: &str) -> String {
+    let longest = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+fn write_code_block(out: &mut String, language: &str, code: &str) {
+    let fence = fence_for(code);
+    let _ = writeln!(out, "{fence}{}", language.trim());
+    let _ = writeln!(out, "{}", code.trim_end());
+    let _ = writeln!(out, "{fence}");
+}
+
+fn write_rules(out: &mut String, rules: &Rules, heading: Option<&str>) {
+    let all: Vec<&String> = rules
+        .global
+        .iter()
+        .chain(&rules.library_own)
+        .chain(&rules.library_team)
+        .collect();
+    if all.is_empty() {
+        return;
+    }
+    if let Some(heading) = heading {
+        let _ = writeln!(out, "{heading}\n");
+    }
+    for rule in all {
+        let _ = writeln!(out, "- {}", rule.trim());
+    }
+    out.push('\n');
+}
+
+fn code_title(snippet: &CodeSnippet) -> &str {
+    if snippet.code_title.trim().is_empty() {
+        snippet.page_title.trim()
+    } else {
+        snippet.code_title.trim()
+    }
+}
+
+fn info_title(snippet: &InfoSnippet) -> &str {
+    snippet
+        .breadcrumb
+        .as_deref()
+        .map(str::trim)
+        .filter(|b| !b.is_empty())
+        .unwrap_or("Documentation")
+}
+
+fn render_sections(response: &QueryDocsResponse, include_sources: bool) -> String {
+    let mut out = String::new();
+
+    if let Some(rules) = &response.rules {
+        write_rules(&mut out, rules, Some("## Rules"));
+    }
+
+    if !response.code_snippets.is_empty() {
+        out.push_str("## Code Examples\n\n");
+        for snippet in &response.code_snippets {
+            let _ = writeln!(out, "### {}\n", code_title(snippet));
+            if include_sources && !snippet.code_id.is_empty() {
+                let _ = writeln!(out, "Source: {}\n", snippet.code_id);
+            }
+            if !snippet.code_description.trim().is_empty() {
+                let _ = writeln!(out, "{}\n", snippet.code_description.trim());
+            }
+            for entry in &snippet.code_list {
+                write_code_block(&mut out, &entry.language, &entry.code);
+                out.push('\n');
+            }
+        }
+    }
+
+    if !response.info_snippets.is_empty() {
+        out.push_str("## Documentation\n\n");
+        for snippet in &response.info_snippets {
+            let _ = writeln!(out, "### {}\n", info_title(snippet));
+            if include_sources && let Some(page_id) = &snippet.page_id {
+                let _ = writeln!(out, "Source: {}\n", page_id);
+            }
+            let _ = writeln!(out, "{}\n", snippet.content.trim());
+        }
+    }
+
+    out.trim_end().to_string()
+}
+
+fn render_compact(response: &QueryDocsResponse) -> String {
+    let mut blocks = Vec::new();
+
+    if let Some(rules) = &response.rules {
+        let mut out = String::new();
+        write_rules(&mut out, rules, None);
+        if !out.is_empty() {
+            blocks.push(out);
+        }
+    }
+
+    for snippet in &response.code_snippets {
+        let mut out = String::new();
+        let _ = writeln!(out, "**{}**", code_title(snippet));
+        for entry in &snippet.code_list {
+            write_code_block(&mut out, &entry.language, &entry.code);
+        }
+        blocks.push(out);
+    }
+
+    for snippet in &response.info_snippets {
+        blocks.push(format!(
+            "**{}**\n{}\n",
+            info_title(snippet),
+            snippet.content.trim()
+        ));
+    }
+
+    blocks
+        .iter()
+        .map(|b| b.trim_end())
+        .collect::<Vec<_>>()
+        .join("\n\n---\n\n")
+}
+
+fn render_docs(response: &QueryDocsResponse, options: RenderOptions) -> String {
+    match options.layout {
+        MarkdownLayout::Sections => render_sections(response, options.include_sources),
+        MarkdownLayout::Compact => render_compact(response),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Helpers to build test data
+// ---------------------------------------------------------------------------
+
+fn sample_response() -> QueryDocsResponse {
+    QueryDocsResponse {
+        code_snippets: vec![CodeSnippet {
+            code_title: "Create a router".to_string(),
+            code_description: " Builds an axum router with one route. ".to_string(),
+            code_id: "https://github.com/tokio-rs/axum/blob/main/README.md".to_string(),
+            page_title: "axum".to_string(),
+            code_list: vec![CodeListEntry {
+                language: "rust".to_string(),
+                code: "let app = Router::new();\n".to_string(),
+            }],
+        }],
+        info_snippets: vec![InfoSnippet {
+            page_id: Some("https://docs.rs/axum".to_string()),
+            breadcrumb: Some("Routing > Basics".to_string()),
+            content: "Routes map paths to handlers.".to_string(),
+        }],
+        rules: Some(Rules {
+            global: vec!["Prefer axum 0.8 APIs".to_string()],
+            library_own: vec![],
+            library_team: vec!["Use tower layers".to_string()],
+        }),
+    }
+}
+
+const SECTIONS: RenderOptions = RenderOptions {
+    layout: MarkdownLayout::Sections,
+    include_sources: true,
+};
+
+// ===========================================================================
+// Tests
+// ===========================================================================
+
+#[test]
+fn test_layout_parsing() {
+    assert_eq!(parse_layout(None), MarkdownLayout::Sections);
+    assert_eq!(parse_layout(Some("COMPACT")), MarkdownLayout::Compact);
+    assert_eq!(parse_layout(Some("tables")), MarkdownLayout::Sections);
+}
+
+#[test]
+fn test_fence_is_longer_than_backticks_in_code() {
+    assert_eq!(fence_for("let x = 1;"), "```");
+    assert_eq!(fence_for("use `inline` code"), "```");
+    assert_eq!(fence_for("```js\nconsole.log(1)\n```"), "````");
+
+    let mut out = String::new();
+    write_code_block(&mut out, " markdown ", "````\nnested\n````\n");
+    assert_eq!(out, "`````markdown\n````\nnested\n````\n`````\n");
+}
+
+#[test]
+fn test_sections_layout() {
+    assert_eq!(
+        render_docs(&sample_response(), SECTIONS),
+        "## Rules\n\n\
+         - Prefer axum 0.8 APIs\n\
+         - Use tower layers\n\n\
+         ## Code Examples\n\n\
+         ### Create a router\n\n\
+         Source: https://github.com/tokio-rs/axum/blob/main/README.md\n\n\
+         Builds an axum router with one route.\n\n\
+         ```rust\n\
+         let app = Router::new();\n\
+         ```\n\n\
+         ## Documentation\n\n\
+         ### Routing > Basics\n\n\
+         Source: https://docs.rs/axum\n\n\
+         Routes map paths to handlers."
+    );
+}
+
+#[test]
+fn test_sources_can_be_omitted() {
+    let text = render_docs(
+        &sample_response(),
+        RenderOptions {
+            include_sources: false,
+            ..SECTIONS
+        },
+    );
+    assert!(!text.contains("Source:"), "{text}");
+    assert!(text.contains("### Create a router"));
+    assert!(text.contains("Builds an axum router with one route."));
+}
+
+#[test]
+fn test_compact_layout() {
+    let text = render_docs(
+        &sample_response(),
+        RenderOptions {
+            layout: MarkdownLayout::Compact,
+            include_sources: true,
+        },
+    );
+    assert_eq!(
+        text,
+        "- Prefer axum 0.8 APIs\n\
+         - Use tower layers\n\n\
+         ---\n\n\
+         **Create a router**\n\
+         ```rust\n\
+         let app = Router::new();\n\
+         ```\n\n\
+         ---\n\n\
+         **Routing > Basics**\n\
+         Routes map paths to handlers."
+    );
+}
+
+#[test]
+fn test_title_fallbacks_and_empty_rules() {
+    let response = QueryDocsResponse {
+        code_snippets: vec![CodeSnippet {
+            code_title: "  ".to_string(),
+            page_title: "Getting Started".to_string(),
+            ..Default::default()
+        }],
+        info_snippets: vec![InfoSnippet {
+            breadcrumb: Some(" ".to_string()),
+            content: "Install the crate.".to_string(),
+            ..Default::default()
+        }],
+        rules: Some(Rules::default()),
+    };
+
+    let text = render_docs(&response, SECTIONS);
+    assert!(!text.contains("## Rules"), "{text}");
+    assert!(text.contains("### Getting Started"), "{text}");
+    assert!(
+        text.contains("### Documentation\n\nInstall the crate."),
+        "{text}"
+    );
+    assert_eq!(render_docs(&QueryDocsResponse::default(), SECTIONS), "");
+}
@@ -0,0 +1,872 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// ---------------------------------------------------------------------------
+// Duplicated types from types.rs that the snippet helpers operate on.
+// ---------------------------------------------------------------------------
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct CodeListEntry {
+    pub language: String,
+    pub code: String,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct CodeSnippet {
+    #[serde(rename = "codeTitle")]
+    pub code_title: String,
+    #[serde(rename = "codeDescription")]
+    pub code_description: String,
+    #[serde(rename = "codeLanguage")]
+    pub code_language: String,
+    #[serde(rename = "codeTokens")]
+    pub code_tokens: f64,
+    #[serde(rename = "codeId")]
+    pub code_id: String,
+    #[serde(rename = "pageTitle")]
+    pub page_title: String,
+    #[serde(rename = "codeList")]
+    pub code_list: Vec<CodeListEntry>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub relevance: Option<f64>,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct InfoSnippet {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "pageId")]
+    pub page_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub breadcrumb: Option<String>,
+    pub content: String,
+    #[serde(rename = "contentTokens")]
+    pub content_tokens: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub relevance: Option<f64>,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct DeduplicationStats {
+    #[serde(rename = "codeSnippets")]
+    pub code_snippets: u32,
+    #[serde(rename = "infoSnippets")]
+    pub info_snippets: u32,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+struct QueryDocsResponse {
+    #[serde(rename = "codeSnippets")]
+    pub code_snippets: Vec<CodeSnippet>,
+    #[serde(rename = "infoSnippets")]
+    pub info_snippets: Vec<InfoSnippet>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub deduplicated: Option<DeduplicationStats>,
+}
+
+#[derive(Default, Debug, Clone)]
+struct LibraryDocs {
+    pub docs: Option<QueryDocsResponse>,
+    pub token_budget: Option<u32>,
+}
+
+#[derive(Default, Debug, Clone)]
+struct QueryDocsArguments {
+    pub languages: Option<Vec<String>>,
+    pub include_info: Option<bool>,
+    pub include_code: Option<bool>,
+    pub max_snippets: Option<u32>,
+    pub rerank: Option<bool>,
+}
+
+impl QueryDocsArguments {
+    /// Whether any snippet filter is set.
+    fn has_snippet_filters(&self) -> bool {
+        self.languages.as_ref().is_some_and(|l| !l.is_empty())
+            || self.include_info == Some(false)
+            || self.include_code == Some(false)
+            || self.max_snippets.is_some()
+    }
+
+    /// Whether the text must be rendered locally from the structured response,
+    /// because snippet filters or re-ranking change it.
+    fn renders_locally(&self) -> bool {
+        self.has_snippet_filters() || self.rerank == Some(true)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Replicated helpers from snippets.rs
+// ---------------------------------------------------------------------------
+
+/// Maps common language aliases to a single name so `ts` matches `typescript`.
+fn canonical_language(language: &str) -> String {
+    let language = language.trim().to_lowercase();
+    match language.as_str() {
+        "js" | "jsx" | "mjs" | "cjs" => "javascript",
+        "ts" | "tsx" | "mts" | "cts" => "typescript",
+        "py" | "python3" => "python",
+        "rs" => "rust",
+        "sh" | "shell" | "zsh" | "console" => "bash",
+        "yml" => "yaml",
+        "golang" => "go",
+        "c++" | "cc" | "cxx" | "hpp" => "cpp",
+        "c#" | "cs" => "csharp",
+        "kt" | "kts" => "kotlin",
+        "rb" => "ruby",
+        _ => return language,
+    }
+    .to_string()
+}
+
+fn keep_languages(snippet: &mut CodeSnippet, languages: &[String]) -> bool {
+    let matches = |language: &str| languages.contains(&canonical_language(language));
+
+    if snippet
+        .code_list
+        .iter()
+        .any(|entry| matches(&entry.language))
+    {
+        snippet.code_list.retain(|entry| matches(&entry.language));
+        true
+    } else {
+        matches(&snippet.code_language)
+    }
+}
+
+/// Applies the `languages`, `includeInfo`, `includeCode` and `maxSnippets`
+/// arguments to a documentation response.
+pub(crate) fn filter(response: &mut QueryDocsResponse, args: &QueryDocsArguments) {
+    if args.include_code == Some(false) {
+        response.code_snippets.clear();
+    }
+    if args.include_info == Some(false) {
+        response.info_snippets.clear();
+    }
+
+    if let Some(languages) = args.languages.as_ref().filter(|l| !l.is_empty()) {
+        let languages: Vec<String> = languages.iter().map(|l| canonical_language(l)).collect();
+        response
+            .code_snippets
+            .retain_mut(|snippet| keep_languages(snippet, &languages));
+    }
+
+    if let Some(max) = args.max_snippets {
+        response.code_snippets.truncate(max as usize);
+        response.info_snippets.truncate(max as usize);
+    }
+}
+
+/// Collapses all whitespace runs so formatting differences do not defeat
+/// duplicate detection.
+fn normalize_text(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Appends `addition` to a ` | `-separated list unless already present.
+fn merge_label(target: &mut String, addition: &str) {
+    let addition = addition.trim();
+    if addition.is_empty() || target.split(" | ").any(|label| label == addition) {
+        return;
+    }
+    if !target.is_empty() {
+        target.push_str(" | ");
+    }
+    target.push_str(addition);
+}
+
+fn code_content_key(snippet: &CodeSnippet) -> String {
+    snippet
+        .code_list
+        .iter()
+        .map(|entry| normalize_text(&entry.code))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Merges code snippets with the same `code_id` or code content, and info
+/// snippets with the same content, recording how many were merged.
+pub(crate) fn deduplicate(response: &mut QueryDocsResponse) {
+    let mut merged_code = 0u32;
+    let mut by_id: HashMap<String, usize> = HashMap::new();
+    let mut by_content: HashMap<String, usize> = HashMap::new();
+    let mut code_snippets: Vec<CodeSnippet> = Vec::with_capacity(response.code_snippets.len());
+
+    for snippet in response.code_snippets.drain(..) {
+        let content = code_content_key(&snippet);
+        let existing = by_id
+            .get(&snippet.code_id)
+            .filter(|_| !snippet.code_id.is_empty())
+            .or_else(|| {
+                by_content
+                    .get(&content)
+                    .filter(|_| !content.trim().is_empty())
+            })
+            .copied();
+
+        match existing {
+            Some(index) => {
+                merge_label(&mut code_snippets[index].page_title, &snippet.page_title);
+                merged_code += 1;
+            }
+            None => {
+                let index = code_snippets.len();
+                if !snippet.code_id.is_empty() {
+                    by_id.insert(snippet.code_id.clone(), index);
+                }
+                if !content.trim().is_empty() {
+                    by_content.insert(content, index);
+                }
+                code_snippets.push(snippet);
+            }
+        }
+    }
+    response.code_snippets = code_snippets;
+
+    let mut merged_info = 0u32;
+    let mut by_content: HashMap<String, usize> = HashMap::new();
+    let mut info_snippets: Vec<InfoSnippet> = Vec::with_capacity(response.info_snippets.len());
+
+    for snippet in response.info_snippets.drain(..) {
+        let content = normalize_text(&snippet.content);
+        match by_content.get(&content).filter(|_| !content.is_empty()) {
+            Some(&index) => {
+                if let Some(breadcrumb) = &snippet.breadcrumb {
+                    merge_label(
+                        info_snippets[index]
+                            .breadcrumb
+                            .get_or_insert_with(String::new),
+                        breadcrumb,
+                    );
+                }
+                merged_info += 1;
+            }
+            None => {
+                by_content.insert(content, info_snippets.len());
+                info_snippets.push(snippet);
+            }
+        }
+    }
+    response.info_snippets = info_snippets;
+
+    if merged_code > 0 || merged_info > 0 {
+        response.deduplicated = Some(DeduplicationStats {
+            code_snippets: merged_code,
+            info_snippets: merged_info,
+        });
+    }
+}
+
+fn response_tokens(response: &QueryDocsResponse) -> f64 {
+    response
+        .code_snippets
+        .iter()
+        .map(|s| s.code_tokens)
+        .chain(response.info_snippets.iter().map(|s| s.content_tokens))
+        .sum()
+}
+
+/// Keeps snippets, alternating between code and info in their original order,
+/// while they fit in `budget` tokens.
+pub(crate) fn trim_to_budget(response: &mut QueryDocsResponse, budget: f64) {
+    let mut used = 0.0;
+    let mut fits = |tokens: f64| {
+        if used + tokens <= budget {
+            used += tokens;
+            true
+        } else {
+            false
+        }
+    };
+
+    let mut keep_code = vec![false; response.code_snippets.len()];
+    let mut keep_info = vec![false; response.info_snippets.len()];
+    for i in 0..keep_code.len().max(keep_info.len()) {
+        if let Some(snippet) = response.code_snippets.get(i) {
+            keep_code[i] = fits(snippet.code_tokens);
+        }
+        if let Some(snippet) = response.info_snippets.get(i) {
+            keep_info[i] = fits(snippet.content_tokens);
+        }
+    }
+
+    let mut keep = keep_code.into_iter();
+    response
+        .code_snippets
+        .retain(|_| keep.next().unwrap_or(false));
+    let mut keep = keep_info.into_iter();
+    response
+        .info_snippets
+        .retain(|_| keep.next().unwrap_or(false));
+}
+
+/// Splits a shared token budget across libraries in proportion to the tokens
+/// each returned, trimming each library to its share. Nothing is trimmed when
+/// everything fits.
+pub(crate) fn allocate_budget(libraries: &mut [LibraryDocs], budget: f64) {
+    let total: f64 = libraries
+        .iter()
+        .filter_map(|library| library.docs.as_ref())
+        .map(response_tokens)
+        .sum();
+    if total <= budget {
+        return;
+    }
+
+    for library in libraries.iter_mut() {
+        if let Some(docs) = library.docs.as_mut() {
+            let share = (budget * response_tokens(docs) / total).floor();
+            trim_to_budget(docs, share);
+            library.token_budget = Some(share as u32);
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Replicated re-ranking helpers from rank.rs (and the query normalization it
+// shares with cache.rs)
+// ---------------------------------------------------------------------------
+
+/// Words dropped from queries during normalization; they rarely change what
+/// documentation a query should return.
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "can", "do", "does", "for", "from", "how",
+    "i", "in", "is", "it", "my", "of", "on", "or", "the", "to", "what", "when", "with",
+];
+
+/// Case-folds the query, collapses punctuation and whitespace, and removes
+/// stopwords.
+pub(crate) fn normalize_query(query: &str) -> String {
+    query
+        .to_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '+' || c == '#' {
+                c
+            } else {
+                ' '
+            }
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .filter(|word| !STOPWORDS.contains(word))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// BM25 term frequency saturation.
+const K1: f64 = 1.2;
+/// BM25 document length normalization.
+const B: f64 = 0.75;
+
+fn tokenize(text: &str) -> Vec<String> {
+    normalize_query(text)
+        .split_whitespace()
+        .map(str::to_string)
+        .collect()
+}
+
+fn code_document(snippet: &CodeSnippet) -> Vec<String> {
+    tokenize(&format!(
+        "{} {} {}",
+        snippet.code_title, snippet.code_description, snippet.page_title
+    ))
+}
+
+fn info_document(snippet: &InfoSnippet) -> Vec<String> {
+    tokenize(&format!(
+        "{} {}",
+        snippet.breadcrumb.as_deref().unwrap_or_default(),
+        snippet.content
+    ))
+}
+
+/// Scores each document against the query with Okapi BM25, using the given
+/// documents as the corpus.
+fn bm25(query: &[String], documents: &[Vec<String>]) -> Vec<f64> {
+    if documents.is_empty() {
+        return Vec::new();
+    }
+
+    let count = documents.len() as f64;
+    let average_length = documents.iter().map(Vec::len).sum::<usize>() as f64 / count;
+
+    let mut document_frequency: HashMap<&str, f64> = HashMap::new();
+    for term in query {
+        if document_frequency.contains_key(term.as_str()) {
+            continue;
+        }
+        let frequency = documents.iter().filter(|d| d.contains(term)).count() as f64;
+        document_frequency.insert(term, frequency);
+    }
+
+    documents
+        .iter()
+        .map(|document| {
+            let length_norm = if average_length > 0.0 {
+                1.0 - B + B * document.len() as f64 / average_length
+            } else {
+                1.0
+            };
+            document_frequency
+                .iter()
+                .map(|(term, frequency)| {
+                    let tf = document.iter().filter(|t| t == term).count() as f64;
+                    let idf = (1.0 + (count - frequency + 0.5) / (frequency + 0.5)).ln();
+                    idf * tf * (K1 + 1.0) / (tf + K1 * length_norm)
+                })
+                .sum()
+        })
+        .collect()
+}
+
+/// Scores every snippet against the query, stores the score (scaled to 0-1)
+/// in its `relevance` field, and sorts both lists by relevance. Code and info
+/// snippets share one corpus so their scores are comparable.
+pub(crate) fn rerank(response: &mut QueryDocsResponse, query: &str) {
+    let query = tokenize(query);
+    let documents: Vec<Vec<String>> = response
+        .code_snippets
+        .iter()
+        .map(code_document)
+        .chain(response.info_snippets.iter().map(info_document))
+        .collect();
+
+    let scores = bm25(&query, &documents);
+    let max = scores.iter().copied().fold(0.0, f64::max);
+    let scale = |score: f64| {
+        let relevance = if max > 0.0 { score / max } else { 0.0 };
+        (relevance * 1000.0).round() / 1000.0
+    };
+
+    let (code_scores, info_scores) = scores.split_at(response.code_snippets.len());
+    for (snippet, score) in response.code_snippets.iter_mut().zip(code_scores) {
+        snippet.relevance = Some(scale(*score));
+    }
+    for (snippet, score) in response.info_snippets.iter_mut().zip(info_scores) {
+        snippet.relevance = Some(scale(*score));
+    }
+
+    let by_relevance =
+        |a: Option<f64>, b: Option<f64>| b.unwrap_or_default().total_cmp(&a.unwrap_or_default());
+    response
+        .code_snippets
+        .sort_by(|a, b| by_relevance(a.relevance, b.relevance));
+    response
+        .info_snippets
+        .sort_by(|a, b| by_relevance(a.relevance, b.relevance));
+}
+
+// ---------------------------------------------------------------------------
+// Helpers to build test data
+// ---------------------------------------------------------------------------
+
+fn code(title: &str, language: &str, blocks: &[(&str, &str)]) -> CodeSnippet {
+    CodeSnippet {
+        code_title: title.to_string(),
+        code_language: language.to_string(),
+        code_id: format!("https://example.com/{}", title.replace(' ', "-")),
+        page_title: "Docs".to_string(),
+        code_list: blocks
+            .iter()
+            .map(|(language, code)| CodeListEntry {
+                language: language.to_string(),
+                code: code.to_string(),
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+fn info(breadcrumb: &str, content: &str) -> InfoSnippet {
+    InfoSnippet {
+        page_id: Some(format!("https://example.com/{}", breadcrumb)),
+        breadcrumb: Some(breadcrumb.to_string()),
+        content: content.to_string(),
+        content_tokens: 10.0,
+        ..Default::default()
+    }
+}
+
+fn sample_response() -> QueryDocsResponse {
+    QueryDocsResponse {
+        code_snippets: vec![
+            code("Rust client", "rust", &[("rust", "let c = Client::new();")]),
+            code(
+                "JS client",
+                "javascript",
+                &[("js", "const c = new Client();")],
+            ),
+            code(
+                "Install",
+                "bash",
+                &[("bash", "cargo add client"), ("bash", "npm i client")],
+            ),
+            code(
+                "Mixed",
+                "typescript",
+                &[("ts", "const x = 1;"), ("rust", "let x = 1;")],
+            ),
+        ],
+        info_snippets: vec![info("Intro", "Hello"), info("Setup", "Install it")],
+        ..Default::default()
+    }
+}
+
+// ===========================================================================
+// Tests
+// ===========================================================================
+
+// --- Filtering ---
+
+#[test]
+fn test_canonical_language_aliases() {
+    assert_eq!(canonical_language("TS"), "typescript");
+    assert_eq!(canonical_language(" js "), "javascript");
+    assert_eq!(canonical_language("rs"), "rust");
+    assert_eq!(canonical_language("Rust"), "rust");
+    assert_eq!(canonical_language("elixir"), "elixir");
+}
+
+#[test]
+fn test_filter_without_arguments_is_noop() {
+    let mut response = sample_response();
+    filter(&mut response, &QueryDocsArguments::default());
+    assert_eq!(response.code_snippets, sample_response().code_snippets);
+    assert_eq!(response.info_snippets, sample_response().info_snippets);
+}
+
+#[test]
+fn test_filters_and_rerank_render_text_locally() {
+    assert!(!QueryDocsArguments::default().renders_locally());
+    assert!(
+        !QueryDocsArguments {
+            include_code: Some(true),
+            rerank: Some(false),
+            languages: Some(Vec::new()),
+            ..Default::default()
+        }
+        .renders_locally()
+    );
+    assert!(
+        QueryDocsArguments {
+            max_snippets: Some(3),
+            ..Default::default()
+        }
+        .renders_locally()
+    );
+    // Re-ranking alone also needs the structured response
+    assert!(
+        QueryDocsArguments {
+            rerank: Some(true),
+            ..Default::default()
+        }
+        .renders_locally()
+    );
+}
+
+#[test]
+fn test_filter_languages_keeps_matching_snippets_and_blocks() {
+    let mut response = sample_response();
+    let args = QueryDocsArguments {
+        languages: Some(vec!["Rust".to_string()]),
+        ..Default::default()
+    };
+    filter(&mut response, &args);
+
+    let titles: Vec<&str> = response
+        .code_snippets
+        .iter()
+        .map(|s| s.code_title.as_str())
+        .collect();
+    assert_eq!(titles, vec!["Rust client", "Mixed"]);
+    assert_eq!(response.code_snippets[1].code_list.len(), 1);
+    assert_eq!(response.code_snippets[1].code_list[0].language, "rust");
+    assert_eq!(
+        response.info_snippets.len(),
+        2,
+        "Info snippets are unaffected"
+    );
+}
+
+#[test]
+fn test_filter_languages_matches_aliases() {
+    let mut response = sample_response();
+    let args = QueryDocsArguments {
+        languages: Some(vec!["javascript".to_string()]),
+        ..Default::default()
+    };
+    filter(&mut response, &args);
+
+    assert_eq!(response.code_snippets.len(), 1);
+    assert_eq!(response.code_snippets[0].code_title, "JS client");
+}
+
+#[test]
+fn test_filter_include_toggles() {
+    let mut response = sample_response();
+    let args = QueryDocsArguments {
+        include_code: Some(false),
+        ..Default::default()
+    };
+    filter(&mut response, &args);
+    assert!(response.code_snippets.is_empty());
+    assert_eq!(response.info_snippets.len(), 2);
+
+    let mut response = sample_response();
+    let args = QueryDocsArguments {
+        include_info: Some(false),
+        ..Default::default()
+    };
+    filter(&mut response, &args);
+    assert_eq!(response.code_snippets.len(), 4);
+    assert!(response.info_snippets.is_empty());
+}
+
+#[test]
+fn test_filter_max_snippets_applies_to_each_list() {
+    let mut response = sample_response();
+    let args = QueryDocsArguments {
+        max_snippets: Some(1),
+        ..Default::default()
+    };
+    filter(&mut response, &args);
+    assert_eq!(response.code_snippets.len(), 1);
+    assert_eq!(response.info_snippets.len(), 1);
+    assert_eq!(response.code_snippets[0].code_title, "Rust client");
+}
+
+// --- Deduplication ---
+
+#[test]
+fn test_deduplicate_without_duplicates_reports_nothing() {
+    let mut response = sample_response();
+    deduplicate(&mut response);
+    assert_eq!(response.code_snippets.len(), 4);
+    assert_eq!(response.info_snippets.len(), 2);
+    assert!(response.deduplicated.is_none());
+}
+
+#[test]
+fn test_deduplicate_merges_same_code_under_different_pages() {
+    let mut first = code("Client", "rust", &[("rust", "let c = Client::new();")]);
+    first.page_title = "Getting Started".to_string();
+    let mut second = code(
+        "Client again",
+        "rust",
+        &[("rust", "let c =\n    Client::new();")],
+    );
+    second.page_title = "API Reference".to_string();
+    let mut response = QueryDocsResponse {
+        code_snippets: vec![first, second],
+        ..Default::default()
+    };
+
+    deduplicate(&mut response);
+
+    assert_eq!(response.code_snippets.len(), 1);
+    assert_eq!(
+        response.code_snippets[0].page_title,
+        "Getting Started | API Reference"
+    );
+    assert_eq!(
+        response.deduplicated,
+        Some(DeduplicationStats {
+            code_snippets: 1,
+            info_snippets: 0,
+        })
+    );
+}
+
+#[test]
+fn test_deduplicate_merges_same_code_id() {
+    let first = code("A", "rust", &[("rust", "fn a() {}")]);
+    let mut second = code("B", "rust", &[("rust", "fn b() {}")]);
+    second.code_id = first.code_id.clone();
+    let mut response = QueryDocsResponse {
+        code_snippets: vec![first, second],
+        ..Default::default()
+    };
+
+    deduplicate(&mut response);
+
+    assert_eq!(response.code_snippets.len(), 1);
+    assert_eq!(response.code_snippets[0].code_title, "A");
+    assert_eq!(response.code_snippets[0].page_title, "Docs");
+}
+
+#[test]
+fn test_deduplicate_merges_info_breadcrumbs() {
+    let mut response = QueryDocsResponse {
+        info_snippets: vec![
+            info("Guide > Setup", "Install the  package."),
+            info("Reference > Install", "Install the package."),
+            info("Guide > Usage", "Call the function."),
+        ],
+        ..Default::default()
+    };
+
+    deduplicate(&mut response);
+
+    assert_eq!(response.info_snippets.len(), 2);
+    assert_eq!(
+        response.info_snippets[0].breadcrumb.as_deref(),
+        Some("Guide > Setup | Reference > Install")
+    );
+    assert_eq!(
+        response.deduplicated,
+        Some(DeduplicationStats {
+            code_snippets: 0,
+            info_snippets: 1,
+        })
+    );
+}
+
+// --- Token budget ---
+
+fn sized(code_tokens: &[f64], info_tokens: &[f64]) -> QueryDocsResponse {
+    QueryDocsResponse {
+        code_snippets: code_tokens
+            .iter()
+            .enumerate()
+            .map(|(i, tokens)| CodeSnippet {
+                code_title: format!("code {i}"),
+                code_tokens: *tokens,
+                ..Default::default()
+            })
+            .collect(),
+        info_snippets: info_tokens
+            .iter()
+            .enumerate()
+            .map(|(i, tokens)| InfoSnippet {
+                content: format!("info {i}"),
+                content_tokens: *tokens,
+                ..Default::default()
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_trim_to_budget_alternates_and_skips_oversized() {
+    let mut response = sized(&[100.0, 500.0, 50.0], &[100.0, 100.0]);
+    trim_to_budget(&mut response, 360.0);
+
+    let code: Vec<&str> = response
+        .code_snippets
+        .iter()
+        .map(|s| s.code_title.as_str())
+        .collect();
+    let info: Vec<&str> = response
+        .info_snippets
+        .iter()
+        .map(|s| s.content.as_str())
+        .collect();
+    assert_eq!(code, vec!["code 0", "code 2"]);
+    assert_eq!(info, vec!["info 0", "info 1"]);
+}
+
+#[test]
+fn test_allocate_budget_is_proportional() {
+    let mut libraries = vec![
+        LibraryDocs {
+            docs: Some(sized(&[300.0, 300.0], &[])),
+            ..Default::default()
+        },
+        LibraryDocs {
+            docs: Some(sized(&[100.0, 100.0], &[])),
+            ..Default::default()
+        },
+        LibraryDocs::default(),
+    ];
+    allocate_budget(&mut libraries, 400.0);
+
+    assert_eq!(libraries[0].token_budget, Some(300));
+    assert_eq!(libraries[0].docs.as_ref().unwrap().code_snippets.len(), 1);
+    assert_eq!(libraries[1].token_budget, Some(100));
+    assert_eq!(libraries[1].docs.as_ref().unwrap().code_snippets.len(), 1);
+    assert_eq!(
+        libraries[2].token_budget, None,
+        "Failed libraries get no share"
+    );
+}
+
+#[test]
+fn test_allocate_budget_leaves_fitting_results_untouched() {
+    let mut libraries = vec![LibraryDocs {
+        docs: Some(sized(&[100.0], &[100.0])),
+        ..Default::default()
+    }];
+    allocate_budget(&mut libraries, 1000.0);
+
+    assert_eq!(libraries[0].token_budget, None);
+    assert_eq!(libraries[0].docs.as_ref().unwrap().code_snippets.len(), 1);
+    assert_eq!(libraries[0].docs.as_ref().unwrap().info_snippets.len(), 1);
+}
+
+// --- Re-ranking ---
+
+#[test]
+fn test_bm25_prefers_documents_with_query_terms() {
+    let query = tokenize("useEffect cleanup");
+    let documents = vec![
+        tokenize("Rendering lists with keys"),
+        tokenize("useEffect cleanup function runs before the next effect"),
+        tokenize("useEffect dependencies"),
+    ];
+    let scores = bm25(&query, &documents);
+
+    assert_eq!(scores[0], 0.0);
+    assert!(scores[1] > scores[2], "{scores:?}");
+    assert!(scores[2] > 0.0, "{scores:?}");
+}
+
+#[test]
+fn test_rerank_sorts_and_scores_snippets() {
+    let mut response = QueryDocsResponse {
+        code_snippets: vec![
+            CodeSnippet {
+                code_title: "Render a list".to_string(),
+                ..Default::default()
+            },
+            CodeSnippet {
+                code_title: "Effect cleanup".to_string(),
+                code_description: "Return a cleanup function from useEffect".to_string(),
+                ..Default::default()
+            },
+        ],
+        info_snippets: vec![
+            info("Reference > Hooks", "useState returns a state value"),
+            info(
+                "Reference > useEffect",
+                "The cleanup function runs on unmount",
+            ),
+        ],
+        ..Default::default()
+    };
+
+    rerank(&mut response, "How do I write a useEffect cleanup?");
+
+    assert_eq!(response.code_snippets[0].code_title, "Effect cleanup");
+    assert_eq!(
+        response.info_snippets[0].breadcrumb.as_deref(),
+        Some("Reference > useEffect")
+    );
+    let top = [
+        response.code_snippets[0].relevance,
+        response.info_snippets[0].relevance,
+    ];
+    assert!(top.contains(&Some(1.0)), "Best snippet scales to 1.0");
+    assert_eq!(response.code_snippets[1].relevance, Some(0.0));
+}
+
+#[test]
+fn test_rerank_empty_response() {
+    let mut response = QueryDocsResponse::default();
+    rerank(&mut response, "anything");
+    assert!(response.code_snippets.is_empty());
+    assert!(response.info_snippets.is_empty());
+}